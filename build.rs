@@ -13,6 +13,10 @@ fn main() {
     // Linkear con nautilus-extension
     println!("cargo:rustc-link-lib=nautilus-extension");
     
+    // Las pruebas de tests/nautilus_module.rs implementan libnautilus-extension en el
+    // propio ejecutable: la extensión cargada con dlopen debe poder resolverla ahí
+    println!("cargo:rustc-link-arg-tests=-Wl,--export-dynamic");
+
     // Re-run si cambian los headers
     println!("cargo:rerun-if-changed=build.rs");
 }
@@ -0,0 +1,766 @@
+//! Protocolo IPC entre G-DriveXP y sus clientes (extensión de Nautilus, debug_ipc)
+//!
+//! Única definición de los mensajes, el framing y las constantes del socket.
+//! Los mensajes se serializan con bincode y viajan como frames
+//! `longitud (u32 big-endian) + payload`.
+//!
+//! El orden de las variantes ES el formato en el cable: bincode codifica el
+//! índice de la variante, así que sólo se pueden añadir variantes al final.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Versión del protocolo definida por este crate (se anuncia en `Hello`)
+///
+/// - v2: handshake `Hello` con capacidades
+/// - v3: `FileStatusData` incluye `last_synced` y `owner`
+/// - v4: `FileStatusData` incluye `error_message`
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// Versión más antigua cuyas respuestas sabe decodificar `decode_response`
+/// (v1: daemons anteriores al handshake, con el formato de v2)
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Tamaño máximo aceptado para un frame.
+///
+/// Las respuestas por lotes de directorios grandes superan con holgura los 4 KiB;
+/// el límite sólo protege de longitudes corruptas.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// GetFileStatus responde con `ExtendedStatus` en lugar de `FileStatus`
+pub const CAP_EXTENDED_STATUS: &str = "extended_status";
+/// Soporta `Subscribe` / `StatusChanged`
+pub const CAP_SUBSCRIBE: &str = "subscribe";
+/// Soporta `GetFileStatusBatch`
+pub const CAP_BATCH_STATUS: &str = "batch_status";
+/// Soporta `GetDirectoryStatus`
+pub const CAP_DIRECTORY_STATUS: &str = "directory_status";
+/// Soporta `GetFileDetails`
+pub const CAP_FILE_DETAILS: &str = "file_details";
+/// Soporta `GetMountPoints`
+pub const CAP_MOUNT_POINTS: &str = "mount_points";
+/// El cliente entiende `SyncStatus::Uploading` / `SyncStatus::Downloading`
+pub const CAP_TRANSFER_PROGRESS: &str = "transfer_progress";
+/// El cliente entiende `SyncStatus::Conflict` y soporta `ResolveConflict`
+pub const CAP_CONFLICTS: &str = "conflicts";
+/// Soporta `SetAvailability` (recursivo y con simulación)
+pub const CAP_RECURSIVE_AVAILABILITY: &str = "recursive_availability";
+/// Soporta `GetFolderSummary`
+pub const CAP_FOLDER_SUMMARY: &str = "folder_summary";
+/// Soporta `Tagged`: varias peticiones en vuelo por conexión, respondidas en cualquier orden
+pub const CAP_MULTIPLEX: &str = "multiplex";
+
+/// Ruta del socket del daemon para el usuario actual: `/run/user/{uid}/gdrivexp.sock`
+pub fn socket_path() -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    PathBuf::from(format!("/run/user/{}/gdrivexp.sock", uid))
+}
+
+// ============================================================
+// Tipos de datos
+// ============================================================
+
+/// Estado de sincronización
+/// - Synced: Local + Drive (verde)
+/// - CloudOnly: Solo en Drive, no descargado (azul)
+/// - LocalOnly: Solo local, pendiente de subir (naranja)
+/// - Error: Error de sincronización (rojo)
+/// - Uploading / Downloading: transferencia en curso con su progreso (0-100)
+/// - Conflict: cambios en local y en Drive (morado)
+/// - Partial: carpeta parcialmente disponible sin conexión
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncStatus {
+    Synced,      // Verde: en local y en drive
+    CloudOnly,   // Azul: solo en drive
+    LocalOnly,   // Naranja: solo local (pending upload)
+    Error,       // Rojo: error de sincronización
+    Unknown,     // Sin emblema
+    /// Subiendo a Drive (sólo para clientes con CAP_TRANSFER_PROGRESS)
+    Uploading { percent: u8 },
+    /// Descargando de Drive (sólo para clientes con CAP_TRANSFER_PROGRESS)
+    Downloading { percent: u8 },
+    /// Modificado en local y en Drive a la vez (sólo para clientes con CAP_CONFLICTS)
+    Conflict,
+    /// Carpeta con contenido en estados distintos (p. ej. parte local, parte solo en Drive).
+    /// Lo calcula el cliente a partir de `FolderSummary`.
+    Partial,
+}
+
+impl SyncStatus {
+    /// Indica si hay una transferencia en curso
+    pub fn is_transferring(&self) -> bool {
+        matches!(self, SyncStatus::Uploading { .. } | SyncStatus::Downloading { .. })
+    }
+}
+
+/// Disponibilidad de un archivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileAvailability {
+    LocalOnline,
+    OnlineOnly,
+    NotTracked,
+}
+
+/// Datos completos de estado del archivo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStatusData {
+    pub status: SyncStatus,
+    pub availability: FileAvailability,
+    pub is_shared: bool,
+    /// Última sincronización completada (segundos Unix), desde v3
+    pub last_synced: Option<i64>,
+    /// Propietario del archivo en Drive (email o nombre visible), desde v3
+    pub owner: Option<String>,
+    /// Motivo del error cuando `status` es `Error`, desde v4
+    pub error_message: Option<String>,
+}
+
+impl FileStatusData {
+    /// Estado usado cuando el daemon no responde o el archivo no está rastreado
+    pub fn unknown() -> Self {
+        Self {
+            status: SyncStatus::Unknown,
+            availability: FileAvailability::NotTracked,
+            is_shared: false,
+            last_synced: None,
+            owner: None,
+            error_message: None,
+        }
+    }
+}
+
+/// Detalle completo de un archivo en Drive (diálogo de Propiedades)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDetails {
+    pub status: SyncStatus,
+    pub availability: FileAvailability,
+    /// ID del archivo en Google Drive
+    pub drive_file_id: Option<String>,
+    /// Última modificación en Drive (segundos Unix)
+    pub remote_modified: Option<i64>,
+    /// Revisión actual en Drive
+    pub revision: Option<String>,
+    /// Tamaño que ocupa en Drive, en bytes
+    pub size_on_drive: Option<u64>,
+    pub is_shared: bool,
+    /// Descripción del estado de compartición ("Compartido con 3 personas", "Cualquiera con el enlace"...)
+    pub sharing: Option<String>,
+    /// Último error de sincronización reportado por el daemon
+    pub last_error: Option<String>,
+}
+
+/// Cómo resolver un conflicto entre la copia local y la de Drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictResolution {
+    /// Sube la versión local y descarta la de Drive
+    KeepLocal,
+    /// Descarga la versión de Drive y descarta la local
+    KeepRemote,
+    /// Conserva ambas: la local se renombra como copia en conflicto
+    KeepBoth,
+}
+
+/// Alcance de un cambio de disponibilidad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AvailabilityImpact {
+    /// Archivos que cambiarán de disponibilidad
+    pub files: u64,
+    /// Bytes que se descargarán (LocalOnline) o se liberarán (OnlineOnly)
+    pub bytes: u64,
+}
+
+/// Recuento recursivo de estados del contenido de una carpeta
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FolderSummary {
+    pub total: u64,
+    pub synced: u64,
+    pub cloud_only: u64,
+    /// Pendientes de subir o con una transferencia en curso
+    pub pending: u64,
+    pub errors: u64,
+    pub conflicts: u64,
+}
+
+impl FolderSummary {
+    /// Estado agregado de la carpeta: cualquier error → Error, cualquier conflicto →
+    /// Conflict, cualquier pendiente → LocalOnly, todo en la nube → CloudOnly,
+    /// todo sincronizado → Synced y el resto → Partial. Vacía → None.
+    pub fn aggregate_status(&self) -> Option<SyncStatus> {
+        if self.total == 0 {
+            None
+        } else if self.errors > 0 {
+            Some(SyncStatus::Error)
+        } else if self.conflicts > 0 {
+            Some(SyncStatus::Conflict)
+        } else if self.pending > 0 {
+            Some(SyncStatus::LocalOnly)
+        } else if self.cloud_only == self.total {
+            Some(SyncStatus::CloudOnly)
+        } else if self.synced == self.total {
+            Some(SyncStatus::Synced)
+        } else {
+            Some(SyncStatus::Partial)
+        }
+    }
+}
+
+// ============================================================
+// Mensajes
+// ============================================================
+
+/// Request IPC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcRequest {
+    GetFileStatus { path: String },
+    Ping,
+    SetOnlineOnly { path: String },
+    SetLocalOnline { path: String },
+    GetFileAvailability { path: String },
+    /// Mantiene la conexión abierta y recibe `StatusChanged` cada vez que cambia un archivo
+    Subscribe,
+    /// Estado de todas las entradas directas de un directorio
+    GetDirectoryStatus { path: String },
+    /// Estado de varios archivos; la respuesta conserva el orden
+    GetFileStatusBatch { paths: Vec<String> },
+    /// Handshake: primer mensaje de cada conexión
+    Hello { protocol_version: u32, capabilities: Vec<String> },
+    /// Detalle completo de un archivo para el diálogo de Propiedades
+    GetFileDetails { path: String },
+    /// Fuerza una sincronización inmediata de la carpeta
+    SyncNow { path: String },
+    /// Pausa la sincronización de la carpeta
+    PauseSync { path: String },
+    /// Raíces de los puntos de montaje gestionados por el daemon
+    GetMountPoints,
+    /// Resuelve el conflicto de un archivo en estado `Conflict`
+    ResolveConflict { path: String, resolution: ConflictResolution },
+    /// Reintenta la sincronización de archivos en estado `Error`
+    RetrySync { paths: Vec<String> },
+    /// Cambia la disponibilidad de un archivo o carpeta.
+    ///
+    /// Con `recursive` se aplica a todo el contenido de la carpeta; con `dry_run`
+    /// sólo se calcula el alcance. Responde `AvailabilityChange`.
+    SetAvailability {
+        path: String,
+        availability: FileAvailability,
+        recursive: bool,
+        dry_run: bool,
+    },
+    /// Recuento recursivo de estados del contenido de una carpeta
+    GetFolderSummary { path: String },
+    /// Petición con identificador (CAP_MULTIPLEX, protocolo v4 o posterior).
+    ///
+    /// El daemon puede atender varias a la vez y responde `IpcResponse::Tagged`
+    /// con el mismo `id`, no necesariamente en el orden de llegada.
+    Tagged { id: u64, request: Box<IpcRequest> },
+}
+
+/// Respuesta IPC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcResponse {
+    FileStatus(SyncStatus),
+    ExtendedStatus(FileStatusData),
+    Pong,
+    Availability(FileAvailability),
+    Success,  // ¡CAMBIADO de Ok a Success!
+    Error { message: String },
+    /// Evento push: los paths cuyo estado cambió (sólo en conexiones suscritas)
+    StatusChanged { paths: Vec<String> },
+    /// Respuesta a GetFileStatusBatch, en el mismo orden que los paths pedidos
+    ExtendedStatusBatch(Vec<FileStatusData>),
+    /// Respuesta a GetDirectoryStatus: (path, estado) por entrada
+    DirectoryStatus(Vec<(String, FileStatusData)>),
+    /// Respuesta al handshake: versión y capacidades del daemon
+    Hello { protocol_version: u32, capabilities: Vec<String> },
+    /// Respuesta a GetFileDetails
+    FileDetails(FileDetails),
+    /// Respuesta a GetMountPoints: paths absolutos de cada raíz
+    MountPoints(Vec<String>),
+    /// Respuesta a SetAvailability: archivos y bytes afectados
+    AvailabilityChange(AvailabilityImpact),
+    /// Respuesta a GetFolderSummary
+    FolderSummary(FolderSummary),
+    /// Respuesta a `IpcRequest::Tagged` con el `id` de la petición
+    Tagged { id: u64, response: Box<IpcResponse> },
+}
+
+// ============================================================
+// Framing
+// ============================================================
+
+/// Serializa un mensaje (sin el prefijo de longitud)
+pub fn encode<T: Serialize>(message: &T) -> io::Result<Vec<u8>> {
+    bincode::serialize(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Deserializa el payload de un frame
+pub fn decode<T: DeserializeOwned>(payload: &[u8]) -> io::Result<T> {
+    bincode::deserialize(payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Envía un frame: longitud (u32 big-endian) + payload ya serializado
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_FRAME_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Mensaje IPC demasiado grande"));
+    }
+    let len = (payload.len() as u32).to_be_bytes();
+    writer.write_all(&len).await?;
+    writer.write_all(payload).await
+}
+
+/// Lee el payload de un frame
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf).await?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Respuesta IPC demasiado grande"));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+/// Serializa y envía un mensaje
+pub async fn write_message<W: AsyncWrite + Unpin, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    write_frame(writer, &encode(message)?).await
+}
+
+/// Deserializa una respuesta según la versión de protocolo negociada con el daemon.
+///
+/// Las versiones fuera de `MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION` son un error:
+/// un daemon más nuevo puede haber cambiado el formato de cualquier respuesta.
+pub fn decode_response(payload: &[u8], protocol_version: u32) -> io::Result<IpcResponse> {
+    match protocol_version {
+        PROTOCOL_VERSION => decode(payload),
+        3 => compat::decode_legacy_response::<compat::FileStatusDataV3>(payload),
+        MIN_PROTOCOL_VERSION..=2 => compat::decode_legacy_response::<compat::FileStatusDataV2>(payload),
+        v => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Versión de protocolo v{} no soportada (v{} a v{})",
+                v, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            ),
+        )),
+    }
+}
+
+/// Lee y deserializa un mensaje
+pub async fn read_message<R: AsyncRead + Unpin, T: DeserializeOwned>(reader: &mut R) -> io::Result<T> {
+    decode(&read_frame(reader).await?)
+}
+
+/// Decodificación de respuestas de daemons anteriores a v4
+mod compat {
+    use super::*;
+
+    // Índices de variante de IpcResponse cuyo payload contiene FileStatusData
+    pub(super) const EXTENDED_STATUS: u32 = 1;
+    pub(super) const EXTENDED_STATUS_BATCH: u32 = 7;
+    pub(super) const DIRECTORY_STATUS: u32 = 8;
+
+    /// FileStatusData tal como lo envían los daemons v1/v2
+    #[derive(Deserialize)]
+    pub(super) struct FileStatusDataV2 {
+        status: SyncStatus,
+        availability: FileAvailability,
+        is_shared: bool,
+    }
+
+    impl From<FileStatusDataV2> for FileStatusData {
+        fn from(v2: FileStatusDataV2) -> Self {
+            Self {
+                status: v2.status,
+                availability: v2.availability,
+                is_shared: v2.is_shared,
+                ..FileStatusData::unknown()
+            }
+        }
+    }
+
+    /// FileStatusData tal como lo envían los daemons v3
+    #[derive(Deserialize)]
+    pub(super) struct FileStatusDataV3 {
+        status: SyncStatus,
+        availability: FileAvailability,
+        is_shared: bool,
+        last_synced: Option<i64>,
+        owner: Option<String>,
+    }
+
+    impl From<FileStatusDataV3> for FileStatusData {
+        fn from(v3: FileStatusDataV3) -> Self {
+            Self {
+                status: v3.status,
+                availability: v3.availability,
+                is_shared: v3.is_shared,
+                last_synced: v3.last_synced,
+                owner: v3.owner,
+                error_message: None,
+            }
+        }
+    }
+
+    /// Decodifica una respuesta cuyo FileStatusData tiene el formato antiguo `L`
+    pub(super) fn decode_legacy_response<L>(payload: &[u8]) -> io::Result<IpcResponse>
+    where
+        L: DeserializeOwned + Into<FileStatusData>,
+    {
+        // bincode codifica el índice de la variante como u32 little-endian
+        let Some((index, rest)) = payload.split_first_chunk::<4>() else {
+            return decode(payload);
+        };
+
+        match u32::from_le_bytes(*index) {
+            EXTENDED_STATUS => {
+                let data: L = decode(rest)?;
+                Ok(IpcResponse::ExtendedStatus(data.into()))
+            }
+            EXTENDED_STATUS_BATCH => {
+                let batch: Vec<L> = decode(rest)?;
+                Ok(IpcResponse::ExtendedStatusBatch(batch.into_iter().map(Into::into).collect()))
+            }
+            DIRECTORY_STATUS => {
+                let entries: Vec<(String, L)> = decode(rest)?;
+                Ok(IpcResponse::DirectoryStatus(
+                    entries.into_iter().map(|(path, data)| (path, data.into())).collect(),
+                ))
+            }
+            _ => decode(payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn status_data() -> FileStatusData {
+        FileStatusData {
+            status: SyncStatus::Uploading { percent: 40 },
+            availability: FileAvailability::LocalOnline,
+            is_shared: true,
+            last_synced: Some(1_700_000_000),
+            owner: Some("ana@example.com".to_string()),
+            error_message: Some("Cuota excedida".to_string()),
+        }
+    }
+
+    /// Índice de cada variante en el cable (el match obliga a revisar esto al añadir una)
+    fn request_index(request: &IpcRequest) -> u32 {
+        match request {
+            IpcRequest::GetFileStatus { .. } => 0,
+            IpcRequest::Ping => 1,
+            IpcRequest::SetOnlineOnly { .. } => 2,
+            IpcRequest::SetLocalOnline { .. } => 3,
+            IpcRequest::GetFileAvailability { .. } => 4,
+            IpcRequest::Subscribe => 5,
+            IpcRequest::GetDirectoryStatus { .. } => 6,
+            IpcRequest::GetFileStatusBatch { .. } => 7,
+            IpcRequest::Hello { .. } => 8,
+            IpcRequest::GetFileDetails { .. } => 9,
+            IpcRequest::SyncNow { .. } => 10,
+            IpcRequest::PauseSync { .. } => 11,
+            IpcRequest::GetMountPoints => 12,
+            IpcRequest::ResolveConflict { .. } => 13,
+            IpcRequest::RetrySync { .. } => 14,
+            IpcRequest::SetAvailability { .. } => 15,
+            IpcRequest::GetFolderSummary { .. } => 16,
+            IpcRequest::Tagged { .. } => 17,
+        }
+    }
+
+    fn response_index(response: &IpcResponse) -> u32 {
+        match response {
+            IpcResponse::FileStatus(_) => 0,
+            IpcResponse::ExtendedStatus(_) => 1,
+            IpcResponse::Pong => 2,
+            IpcResponse::Availability(_) => 3,
+            IpcResponse::Success => 4,
+            IpcResponse::Error { .. } => 5,
+            IpcResponse::StatusChanged { .. } => 6,
+            IpcResponse::ExtendedStatusBatch(_) => 7,
+            IpcResponse::DirectoryStatus(_) => 8,
+            IpcResponse::Hello { .. } => 9,
+            IpcResponse::FileDetails(_) => 10,
+            IpcResponse::MountPoints(_) => 11,
+            IpcResponse::AvailabilityChange(_) => 12,
+            IpcResponse::FolderSummary(_) => 13,
+            IpcResponse::Tagged { .. } => 14,
+        }
+    }
+
+    fn all_requests() -> Vec<IpcRequest> {
+        let path = || "/home/ana/Drive/informe final.pdf".to_string();
+        vec![
+            IpcRequest::GetFileStatus { path: path() },
+            IpcRequest::Ping,
+            IpcRequest::SetOnlineOnly { path: path() },
+            IpcRequest::SetLocalOnline { path: path() },
+            IpcRequest::GetFileAvailability { path: path() },
+            IpcRequest::Subscribe,
+            IpcRequest::GetDirectoryStatus { path: path() },
+            IpcRequest::GetFileStatusBatch { paths: vec![path(), "/b".to_string()] },
+            IpcRequest::Hello {
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: vec![CAP_EXTENDED_STATUS.to_string(), CAP_MULTIPLEX.to_string()],
+            },
+            IpcRequest::GetFileDetails { path: path() },
+            IpcRequest::SyncNow { path: path() },
+            IpcRequest::PauseSync { path: path() },
+            IpcRequest::GetMountPoints,
+            IpcRequest::ResolveConflict {
+                path: path(),
+                resolution: ConflictResolution::KeepBoth,
+            },
+            IpcRequest::RetrySync { paths: vec![path()] },
+            IpcRequest::SetAvailability {
+                path: path(),
+                availability: FileAvailability::OnlineOnly,
+                recursive: true,
+                dry_run: true,
+            },
+            IpcRequest::GetFolderSummary { path: path() },
+            IpcRequest::Tagged {
+                id: u64::MAX,
+                request: Box::new(IpcRequest::GetFileStatus { path: path() }),
+            },
+        ]
+    }
+
+    fn all_responses() -> Vec<IpcResponse> {
+        vec![
+            IpcResponse::FileStatus(SyncStatus::Downloading { percent: 99 }),
+            IpcResponse::ExtendedStatus(status_data()),
+            IpcResponse::Pong,
+            IpcResponse::Availability(FileAvailability::NotTracked),
+            IpcResponse::Success,
+            IpcResponse::Error { message: "Sin conexión".to_string() },
+            IpcResponse::StatusChanged { paths: vec!["/a".to_string(), "/b".to_string()] },
+            IpcResponse::ExtendedStatusBatch(vec![status_data(), FileStatusData::unknown()]),
+            IpcResponse::DirectoryStatus(vec![("/a".to_string(), status_data())]),
+            IpcResponse::Hello {
+                protocol_version: 3,
+                capabilities: vec![CAP_SUBSCRIBE.to_string()],
+            },
+            IpcResponse::FileDetails(FileDetails {
+                status: SyncStatus::Conflict,
+                availability: FileAvailability::OnlineOnly,
+                drive_file_id: Some("1AbC".to_string()),
+                remote_modified: Some(-1),
+                revision: None,
+                size_on_drive: Some(u64::MAX),
+                is_shared: false,
+                sharing: Some("Cualquiera con el enlace".to_string()),
+                last_error: None,
+            }),
+            IpcResponse::MountPoints(vec!["/home/ana/Drive".to_string()]),
+            IpcResponse::AvailabilityChange(AvailabilityImpact { files: 3, bytes: 4096 }),
+            IpcResponse::FolderSummary(FolderSummary {
+                total: 6,
+                synced: 1,
+                cloud_only: 1,
+                pending: 1,
+                errors: 1,
+                conflicts: 1,
+            }),
+            IpcResponse::Tagged {
+                id: 7,
+                response: Box::new(IpcResponse::Pong),
+            },
+        ]
+    }
+
+    #[test]
+    fn every_request_round_trips_with_a_stable_variant_index() {
+        let requests = all_requests();
+        assert_eq!(requests.len(), 18);
+        for request in requests {
+            let payload = encode(&request).unwrap();
+            assert_eq!(payload[..4], request_index(&request).to_le_bytes(), "{:?}", request);
+            assert_eq!(decode::<IpcRequest>(&payload).unwrap(), request);
+        }
+    }
+
+    #[test]
+    fn every_response_round_trips_with_a_stable_variant_index() {
+        let responses = all_responses();
+        assert_eq!(responses.len(), 15);
+        for response in responses {
+            let payload = encode(&response).unwrap();
+            assert_eq!(payload[..4], response_index(&response).to_le_bytes(), "{:?}", response);
+            assert_eq!(decode::<IpcResponse>(&payload).unwrap(), response);
+            assert_eq!(decode_response(&payload, PROTOCOL_VERSION).unwrap(), response);
+        }
+    }
+
+    #[test]
+    fn legacy_indices_match_the_enum() {
+        let status = IpcResponse::ExtendedStatus(FileStatusData::unknown());
+        let batch = IpcResponse::ExtendedStatusBatch(Vec::new());
+        let directory = IpcResponse::DirectoryStatus(Vec::new());
+        assert_eq!(response_index(&status), compat::EXTENDED_STATUS);
+        assert_eq!(response_index(&batch), compat::EXTENDED_STATUS_BATCH);
+        assert_eq!(response_index(&directory), compat::DIRECTORY_STATUS);
+    }
+
+    #[test]
+    fn pinned_bytes() {
+        let request = IpcRequest::GetFileStatus { path: "/a".to_string() };
+        assert_eq!(
+            encode(&request).unwrap(),
+            [
+                0, 0, 0, 0, // GetFileStatus
+                2, 0, 0, 0, 0, 0, 0, 0, b'/', b'a', // path: u64 + bytes
+            ]
+        );
+
+        let response = IpcResponse::ExtendedStatus(FileStatusData {
+            status: SyncStatus::Synced,
+            availability: FileAvailability::OnlineOnly,
+            is_shared: true,
+            last_synced: Some(1),
+            owner: None,
+            error_message: Some("x".to_string()),
+        });
+        assert_eq!(
+            encode(&response).unwrap(),
+            [
+                1, 0, 0, 0, // ExtendedStatus
+                0, 0, 0, 0, // Synced
+                1, 0, 0, 0, // OnlineOnly
+                1, // is_shared
+                1, 1, 0, 0, 0, 0, 0, 0, 0, // last_synced: Some(1)
+                0, // owner: None
+                1, 1, 0, 0, 0, 0, 0, 0, 0, b'x', // error_message: Some("x")
+            ]
+        );
+
+        let progress = SyncStatus::Uploading { percent: 40 };
+        assert_eq!(encode(&progress).unwrap(), [5, 0, 0, 0, 40]);
+
+        let tagged = IpcRequest::Tagged {
+            id: 258,
+            request: Box::new(IpcRequest::Ping),
+        };
+        assert_eq!(encode(&tagged).unwrap(), [17, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+    }
+
+    /// Payload de `variant` con los campos ya serializados de un daemon antiguo
+    fn legacy_payload(variant: u32, body: Vec<u8>) -> Vec<u8> {
+        let mut payload = variant.to_le_bytes().to_vec();
+        payload.extend(body);
+        payload
+    }
+
+    #[test]
+    fn decodes_v2_payloads() {
+        let v2 = (SyncStatus::Synced, FileAvailability::LocalOnline, true);
+        let expected = FileStatusData {
+            status: SyncStatus::Synced,
+            availability: FileAvailability::LocalOnline,
+            is_shared: true,
+            ..FileStatusData::unknown()
+        };
+
+        let single = legacy_payload(1, encode(&v2).unwrap());
+        assert_eq!(decode_response(&single, 2).unwrap(), IpcResponse::ExtendedStatus(expected.clone()));
+        // Antes del handshake la versión se desconoce y se asume la más antigua
+        assert_eq!(decode_response(&single, 1).unwrap(), IpcResponse::ExtendedStatus(expected.clone()));
+
+        let batch = legacy_payload(7, encode(&vec![v2, v2]).unwrap());
+        assert_eq!(
+            decode_response(&batch, 2).unwrap(),
+            IpcResponse::ExtendedStatusBatch(vec![expected.clone(), expected.clone()])
+        );
+
+        let directory = legacy_payload(8, encode(&vec![("/a".to_string(), v2)]).unwrap());
+        assert_eq!(
+            decode_response(&directory, 2).unwrap(),
+            IpcResponse::DirectoryStatus(vec![("/a".to_string(), expected)])
+        );
+
+        // El resto de respuestas no cambió de formato
+        let pong = encode(&IpcResponse::Pong).unwrap();
+        assert_eq!(decode_response(&pong, 2).unwrap(), IpcResponse::Pong);
+        // Un payload v2 no es un FileStatusData v4 válido
+        assert!(decode::<IpcResponse>(&single).is_err());
+    }
+
+    #[test]
+    fn decodes_v3_payloads() {
+        let v3 = (
+            SyncStatus::Error,
+            FileAvailability::OnlineOnly,
+            false,
+            Some(42i64),
+            Some("ana@example.com".to_string()),
+        );
+        let expected = FileStatusData {
+            status: SyncStatus::Error,
+            availability: FileAvailability::OnlineOnly,
+            is_shared: false,
+            last_synced: Some(42),
+            owner: Some("ana@example.com".to_string()),
+            error_message: None,
+        };
+
+        let single = legacy_payload(1, encode(&v3).unwrap());
+        assert_eq!(decode_response(&single, 3).unwrap(), IpcResponse::ExtendedStatus(expected.clone()));
+
+        let batch = legacy_payload(7, encode(&vec![v3.clone()]).unwrap());
+        assert_eq!(decode_response(&batch, 3).unwrap(), IpcResponse::ExtendedStatusBatch(vec![expected]));
+    }
+
+    #[test]
+    fn unknown_versions_are_not_decoded() {
+        // Ni siquiera lo que hoy no cambia de formato: un daemon más nuevo pudo cambiarlo
+        let pong = encode(&IpcResponse::Pong).unwrap();
+        for version in [MIN_PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            let error = decode_response(&pong, version).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "v{}", version);
+        }
+    }
+
+    #[test]
+    fn frames_are_length_prefixed_big_endian() {
+        let mut buffer = Vec::new();
+        block_on(write_message(&mut buffer, &IpcRequest::Ping)).unwrap();
+        assert_eq!(buffer, [0, 0, 0, 4, 1, 0, 0, 0]);
+
+        let mut reader = buffer.as_slice();
+        let request: IpcRequest = block_on(read_message(&mut reader)).unwrap();
+        assert_eq!(request, IpcRequest::Ping);
+    }
+
+    #[test]
+    fn read_frame_rejects_oversized_frames() {
+        let mut input = ((MAX_FRAME_SIZE + 1) as u32).to_be_bytes().to_vec();
+        input.extend([0; 16]);
+        let error = block_on(read_frame(&mut input.as_slice())).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = block_on(write_frame(&mut Vec::new(), &vec![0; MAX_FRAME_SIZE + 1])).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn read_frame_fails_on_truncated_payload() {
+        let mut input = 8u32.to_be_bytes().to_vec();
+        input.extend([1, 2, 3]);
+        let error = block_on(read_frame(&mut input.as_slice())).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
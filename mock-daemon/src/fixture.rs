@@ -0,0 +1,218 @@
+//! Guion del daemon simulado (fichero TOML)
+//!
+//! ```toml
+//! protocol_version = 4                # 1 = daemon anterior al handshake
+//! capabilities = ["extended_status"]  # por defecto, todas
+//! mount_points = ["/home/ana/GoogleDrive"]
+//!
+//! [[file]]
+//! path = "/home/ana/GoogleDrive/informe.pdf"
+//! status = "Synced"                   # o { Uploading = { percent = 40 } }
+//! availability = "LocalOnline"
+//!
+//! [[fault]]
+//! request = "GetFileStatus"           # variante de IpcRequest, o "*"
+//! path = "/home/ana/GoogleDrive/informe.pdf"
+//! delay_ms = 300
+//! action = "disconnect"
+//! times = 1                           # 0 = siempre
+//! ```
+
+use gdrivexp_ipc_proto::{
+    FileAvailability, FileDetails, FileStatusData, FolderSummary, SyncStatus, CAP_BATCH_STATUS,
+    CAP_CONFLICTS, CAP_DIRECTORY_STATUS, CAP_EXTENDED_STATUS, CAP_FILE_DETAILS, CAP_FOLDER_SUMMARY,
+    CAP_MOUNT_POINTS, CAP_MULTIPLEX, CAP_RECURSIVE_AVAILABILITY, CAP_SUBSCRIBE, PROTOCOL_VERSION,
+};
+use serde::Deserialize;
+use std::io;
+use std::path::Path;
+
+/// Comportamiento completo del daemon simulado
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    /// Versión anunciada en `Hello`; con 1 el daemon no entiende el handshake
+    #[serde(default = "default_protocol_version")]
+    pub protocol_version: u32,
+    #[serde(default = "all_capabilities")]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub mount_points: Vec<String>,
+    #[serde(default, rename = "file")]
+    pub files: Vec<FileEntry>,
+    /// Recuentos fijos para `GetFolderSummary`; sin entrada se calculan con `files`
+    #[serde(default, rename = "folder")]
+    pub folders: Vec<FolderEntry>,
+    #[serde(default, rename = "fault")]
+    pub faults: Vec<Fault>,
+    /// Cambios que se envían a cada suscripción
+    #[serde(default, rename = "event")]
+    pub events: Vec<Event>,
+}
+
+/// Archivo conocido por el daemon
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileEntry {
+    pub path: String,
+    pub status: SyncStatus,
+    #[serde(default = "default_availability")]
+    pub availability: FileAvailability,
+    #[serde(default)]
+    pub shared: bool,
+    pub last_synced: Option<i64>,
+    pub owner: Option<String>,
+    /// Motivo del error (`status = "Error"`)
+    pub error: Option<String>,
+    pub drive_id: Option<String>,
+    pub remote_modified: Option<i64>,
+    pub revision: Option<String>,
+    pub size: Option<u64>,
+    pub sharing: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FolderEntry {
+    pub path: String,
+    #[serde(flatten)]
+    pub summary: FolderSummary,
+}
+
+/// Fallo inyectado en las peticiones que coinciden
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fault {
+    /// Nombre de la variante de `IpcRequest` ("GetFileStatus", "Hello"...) o "*"
+    #[serde(default = "any_request")]
+    pub request: String,
+    /// Sólo peticiones que incluyen este path (o URI)
+    pub path: Option<String>,
+    /// Espera antes de aplicar la acción
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub action: FaultAction,
+    /// Mensaje de `action = "error"`
+    pub message: Option<String>,
+    /// Veces que se aplica; 0 = siempre
+    #[serde(default = "default_times")]
+    pub times: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultAction {
+    /// Responde con normalidad (tras `delay_ms`)
+    #[default]
+    Respond,
+    /// Responde `IpcResponse::Error`
+    Error,
+    /// Cierra la conexión sin responder
+    Disconnect,
+    /// Envía un frame que no es un `IpcResponse` válido
+    Malformed,
+    /// Envía la cabecera de un frame y parte del payload, y cierra
+    Truncated,
+    /// Anuncia un frame mayor que `MAX_FRAME_SIZE`
+    Oversized,
+    /// No responde nunca (la conexión sigue abierta)
+    Silence,
+}
+
+/// `StatusChanged` enviado a las suscripciones `after_ms` después de suscribirse
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    #[serde(default)]
+    pub after_ms: u64,
+    pub paths: Vec<String>,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            protocol_version: default_protocol_version(),
+            capabilities: all_capabilities(),
+            mount_points: Vec::new(),
+            files: Vec::new(),
+            folders: Vec::new(),
+            faults: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+impl FileEntry {
+    pub fn status_data(&self) -> FileStatusData {
+        FileStatusData {
+            status: self.status,
+            availability: self.availability,
+            is_shared: self.shared,
+            last_synced: self.last_synced,
+            owner: self.owner.clone(),
+            error_message: self.error.clone(),
+        }
+    }
+
+    pub fn details(&self) -> FileDetails {
+        FileDetails {
+            status: self.status,
+            availability: self.availability,
+            drive_file_id: self.drive_id.clone(),
+            remote_modified: self.remote_modified,
+            revision: self.revision.clone(),
+            size_on_drive: self.size,
+            is_shared: self.shared,
+            sharing: self.sharing.clone(),
+            last_error: self.error.clone(),
+        }
+    }
+}
+
+fn default_protocol_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+fn all_capabilities() -> Vec<String> {
+    [
+        CAP_EXTENDED_STATUS,
+        CAP_SUBSCRIBE,
+        CAP_BATCH_STATUS,
+        CAP_DIRECTORY_STATUS,
+        CAP_FILE_DETAILS,
+        CAP_MOUNT_POINTS,
+        CAP_CONFLICTS,
+        CAP_RECURSIVE_AVAILABILITY,
+        CAP_FOLDER_SUMMARY,
+        CAP_MULTIPLEX,
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect()
+}
+
+fn default_availability() -> FileAvailability {
+    FileAvailability::LocalOnline
+}
+
+fn any_request() -> String {
+    "*".to_string()
+}
+
+fn default_times() -> u32 {
+    1
+}
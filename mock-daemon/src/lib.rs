@@ -0,0 +1,710 @@
+//! Daemon de G-DriveXP simulado para desarrollo local y pruebas
+//!
+//! Sirve el protocolo real (`gdrivexp-ipc-proto`) en un socket Unix con los
+//! archivos, capacidades y fallos de un [`Fixture`]. Cada `MockDaemon` corre
+//! en su propio hilo, así se puede usar desde código síncrono y desde clientes
+//! que no son `Send` (como `IpcClient`).
+
+mod fixture;
+
+pub use fixture::{Event, Fault, FaultAction, FileEntry, Fixture, FolderEntry};
+
+use gdrivexp_ipc_proto::{
+    self as proto, AvailabilityImpact, FileAvailability, FileStatusData, FolderSummary, IpcRequest,
+    IpcResponse, SyncStatus, CAP_BATCH_STATUS, CAP_CONFLICTS, CAP_DIRECTORY_STATUS,
+    CAP_EXTENDED_STATUS, CAP_FILE_DETAILS, CAP_FOLDER_SUMMARY, CAP_MOUNT_POINTS,
+    CAP_MULTIPLEX, CAP_RECURSIVE_AVAILABILITY, CAP_SUBSCRIBE, MAX_FRAME_SIZE,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, Mutex as AsyncMutex};
+
+/// Daemon simulado en marcha; se detiene (y borra el socket) al soltarlo
+pub struct MockDaemon {
+    socket_path: PathBuf,
+    /// Directorio temporal propio, si lo creamos nosotros
+    temp_dir: Option<PathBuf>,
+    state: Arc<State>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockDaemon {
+    /// Arranca en un socket dentro de un directorio temporal nuevo
+    pub fn start(fixture: Fixture) -> io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "gdrivexp-mock-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+
+        let mut daemon = Self::start_at(fixture, &dir.join("gdrivexp.sock"))?;
+        daemon.temp_dir = Some(dir);
+        Ok(daemon)
+    }
+
+    /// Arranca en `socket_path` (sustituye un socket anterior que ya no responde)
+    pub fn start_at(fixture: Fixture, socket_path: &Path) -> io::Result<Self> {
+        if socket_path.exists() {
+            if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} ya tiene un daemon escuchando", socket_path.display()),
+                ));
+            }
+            std::fs::remove_file(socket_path)?;
+        }
+
+        // El listener se crea aquí para que los errores lleguen al llamador
+        let listener = std::os::unix::net::UnixListener::bind(socket_path)?;
+        listener.set_nonblocking(true)?;
+
+        let state = Arc::new(State::new(fixture));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_state = state.clone();
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("mock-daemon".to_string())
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("tokio runtime");
+                rt.block_on(async move {
+                    let listener = UnixListener::from_std(listener).expect("listener");
+                    serve(listener, thread_state, thread_stop).await;
+                });
+            })?;
+
+        Ok(Self {
+            socket_path: socket_path.to_path_buf(),
+            temp_dir: None,
+            state,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Peticiones recibidas hasta ahora, en orden de llegada
+    pub fn requests(&self) -> Vec<IpcRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Conexiones aceptadas hasta ahora (cada reconexión cuenta)
+    pub fn connections(&self) -> usize {
+        self.state.connections.load(Ordering::Relaxed)
+    }
+
+    /// Suscripciones activas (`Subscribe` ya atendido)
+    pub fn subscribers(&self) -> usize {
+        self.state.subscribers.lock().unwrap().len()
+    }
+
+    /// Cambia el estado de un archivo y lo notifica a las suscripciones
+    pub fn set_status(&self, path: &str, status: SyncStatus) {
+        self.state.update(path, |entry| entry.status = status);
+    }
+
+    /// Bloquea hasta que el daemon se detenga (binario `mock_daemon`)
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockDaemon {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            // Despierta el accept pendiente para que vea `stop`
+            let _ = std::os::unix::net::UnixStream::connect(&self.socket_path);
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.socket_path);
+        if let Some(dir) = &self.temp_dir {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+// ============================================================
+// Estado compartido entre conexiones
+// ============================================================
+
+struct State {
+    protocol_version: u32,
+    capabilities: Vec<String>,
+    mount_points: Vec<String>,
+    folders: Vec<FolderEntry>,
+    events: Vec<Event>,
+    /// Fallos con las aplicaciones que les quedan (`None` = siempre)
+    faults: Mutex<Vec<(Fault, Option<u32>)>>,
+    files: Mutex<BTreeMap<String, FileEntry>>,
+    requests: Mutex<Vec<IpcRequest>>,
+    connections: AtomicUsize,
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<Vec<String>>>>,
+}
+
+impl State {
+    fn new(fixture: Fixture) -> Self {
+        let files = fixture
+            .files
+            .into_iter()
+            .map(|entry| (normalize(&entry.path), entry))
+            .collect();
+        let faults = fixture
+            .faults
+            .into_iter()
+            .map(|fault| {
+                let remaining = (fault.times > 0).then_some(fault.times);
+                (fault, remaining)
+            })
+            .collect();
+
+        Self {
+            protocol_version: fixture.protocol_version,
+            capabilities: fixture.capabilities,
+            mount_points: fixture.mount_points,
+            folders: fixture.folders,
+            events: fixture.events,
+            faults: Mutex::new(faults),
+            files: Mutex::new(files),
+            requests: Mutex::new(Vec::new()),
+            connections: AtomicUsize::new(0),
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// Primer fallo que coincide con la petición (y descuenta una aplicación)
+    fn take_fault(&self, request: &IpcRequest) -> Option<Fault> {
+        let name = request_name(request);
+        let paths = request_paths(request);
+        let mut faults = self.faults.lock().unwrap();
+
+        let (fault, remaining) = faults.iter_mut().find(|(fault, remaining)| {
+            remaining != &Some(0)
+                && (fault.request == "*" || fault.request == name)
+                && fault
+                    .path
+                    .as_deref()
+                    .is_none_or(|path| paths.iter().any(|p| normalize(p) == normalize(path)))
+        })?;
+        if let Some(remaining) = remaining {
+            *remaining -= 1;
+        }
+        Some(fault.clone())
+    }
+
+    fn status_data(&self, path: &str) -> FileStatusData {
+        self.files
+            .lock()
+            .unwrap()
+            .get(&normalize(path))
+            .map_or_else(FileStatusData::unknown, FileEntry::status_data)
+    }
+
+    fn update(&self, path: &str, change: impl FnOnce(&mut FileEntry)) -> bool {
+        let updated = match self.files.lock().unwrap().get_mut(&normalize(path)) {
+            Some(entry) => {
+                change(entry);
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.notify(vec![path.to_string()]);
+        }
+        updated
+    }
+
+    fn notify(&self, paths: Vec<String>) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(paths.clone()).is_ok());
+    }
+
+    /// Archivos dentro de `dir` (directos o a cualquier profundidad)
+    fn files_under(&self, dir: &str, recursive: bool) -> Vec<FileEntry> {
+        let prefix = format!("{}/", normalize(dir).trim_end_matches('/'));
+        self.files
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(path, _)| {
+                path.strip_prefix(&prefix)
+                    .is_some_and(|rest| recursive || !rest.contains('/'))
+            })
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    }
+
+    fn folder_summary(&self, path: &str) -> FolderSummary {
+        if let Some(folder) = self.folders.iter().find(|f| normalize(&f.path) == normalize(path)) {
+            return folder.summary;
+        }
+
+        let mut summary = FolderSummary::default();
+        for entry in self.files_under(path, true) {
+            summary.total += 1;
+            match entry.status {
+                SyncStatus::Synced => summary.synced += 1,
+                SyncStatus::CloudOnly => summary.cloud_only += 1,
+                SyncStatus::LocalOnly | SyncStatus::Uploading { .. } | SyncStatus::Downloading { .. } => {
+                    summary.pending += 1
+                }
+                SyncStatus::Error => summary.errors += 1,
+                SyncStatus::Conflict => summary.conflicts += 1,
+                SyncStatus::Unknown | SyncStatus::Partial => {}
+            }
+        }
+        summary
+    }
+
+    /// Respuesta normal a una petición (sin fallos inyectados)
+    fn respond(&self, request: IpcRequest) -> IpcResponse {
+        if let Some(capability) = required_capability(&request) {
+            if !self.supports(capability) {
+                return unknown_request();
+            }
+        }
+
+        match request {
+            IpcRequest::Hello { .. } if self.protocol_version < 2 => unknown_request(),
+            IpcRequest::Hello { .. } => IpcResponse::Hello {
+                protocol_version: self.protocol_version,
+                capabilities: self.capabilities.clone(),
+            },
+            IpcRequest::Ping => IpcResponse::Pong,
+            IpcRequest::GetFileStatus { path } if self.supports(CAP_EXTENDED_STATUS) => {
+                IpcResponse::ExtendedStatus(self.status_data(&path))
+            }
+            IpcRequest::GetFileStatus { path } => IpcResponse::FileStatus(self.status_data(&path).status),
+            IpcRequest::GetFileAvailability { path } => {
+                IpcResponse::Availability(self.status_data(&path).availability)
+            }
+            IpcRequest::SetOnlineOnly { path } => {
+                self.set_availability(&path, FileAvailability::OnlineOnly);
+                IpcResponse::Success
+            }
+            IpcRequest::SetLocalOnline { path } => {
+                self.set_availability(&path, FileAvailability::LocalOnline);
+                IpcResponse::Success
+            }
+            IpcRequest::GetFileStatusBatch { paths } => {
+                IpcResponse::ExtendedStatusBatch(paths.iter().map(|p| self.status_data(p)).collect())
+            }
+            IpcRequest::GetDirectoryStatus { path } => IpcResponse::DirectoryStatus(
+                self.files_under(&path, false)
+                    .into_iter()
+                    .map(|entry| {
+                        let data = entry.status_data();
+                        (entry.path, data)
+                    })
+                    .collect(),
+            ),
+            IpcRequest::GetFileDetails { path } => match self.files.lock().unwrap().get(&normalize(&path)) {
+                Some(entry) => IpcResponse::FileDetails(entry.details()),
+                None => IpcResponse::Error {
+                    message: format!("Archivo no rastreado: {}", path),
+                },
+            },
+            IpcRequest::GetMountPoints => IpcResponse::MountPoints(self.mount_points.clone()),
+            IpcRequest::SyncNow { .. } | IpcRequest::PauseSync { .. } => IpcResponse::Success,
+            IpcRequest::ResolveConflict { path, .. } => {
+                self.update(&path, |entry| entry.status = SyncStatus::Synced);
+                IpcResponse::Success
+            }
+            IpcRequest::RetrySync { paths } => {
+                for path in &paths {
+                    self.update(path, |entry| {
+                        entry.status = SyncStatus::Synced;
+                        entry.error = None;
+                    });
+                }
+                IpcResponse::Success
+            }
+            IpcRequest::SetAvailability {
+                path,
+                availability,
+                recursive,
+                dry_run,
+            } => {
+                let mut affected: Vec<FileEntry> = if recursive {
+                    self.files_under(&path, true)
+                } else {
+                    Vec::new()
+                };
+                if let Some(entry) = self.files.lock().unwrap().get(&normalize(&path)) {
+                    affected.push(entry.clone());
+                }
+                affected.retain(|entry| entry.availability != availability);
+
+                let impact = AvailabilityImpact {
+                    files: affected.len() as u64,
+                    bytes: affected.iter().filter_map(|entry| entry.size).sum(),
+                };
+                if !dry_run {
+                    for entry in &affected {
+                        self.set_availability(&entry.path, availability);
+                    }
+                }
+                IpcResponse::AvailabilityChange(impact)
+            }
+            IpcRequest::GetFolderSummary { path } => IpcResponse::FolderSummary(self.folder_summary(&path)),
+            // Se atiende en handle_connection
+            IpcRequest::Subscribe => IpcResponse::Success,
+            // Anidada, o sin CAP_MULTIPLEX / protocolo v4
+            IpcRequest::Tagged { .. } => unknown_request(),
+        }
+    }
+
+    fn set_availability(&self, path: &str, availability: FileAvailability) {
+        self.update(path, |entry| {
+            entry.availability = availability;
+            entry.status = match (availability, entry.status) {
+                (FileAvailability::OnlineOnly, SyncStatus::Synced) => SyncStatus::CloudOnly,
+                (FileAvailability::LocalOnline, SyncStatus::CloudOnly) => SyncStatus::Synced,
+                (_, status) => status,
+            };
+        });
+    }
+}
+
+// ============================================================
+// Servidor
+// ============================================================
+
+/// Atiende conexiones hasta que se active `stop`
+async fn serve(listener: UnixListener, state: Arc<State>, stop: Arc<AtomicBool>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(_) => continue,
+        };
+        if stop.load(Ordering::Acquire) {
+            return;
+        }
+        tokio::spawn(handle_connection(stream, state.clone()));
+    }
+}
+
+/// Mitad de escritura compartida por las respuestas en vuelo de una conexión
+type Writer = Arc<AsyncMutex<OwnedWriteHalf>>;
+
+/// Qué se envía en respuesta a una petición, una vez aplicados los fallos
+enum Reply {
+    Response(IpcResponse),
+    /// Bytes ya enmarcados (frame corrupto o incompleto); con `close` se cierra después
+    Raw { bytes: Vec<u8>, close: bool },
+    Close,
+    Nothing,
+}
+
+async fn handle_connection(stream: UnixStream, state: Arc<State>) {
+    state.connections.fetch_add(1, Ordering::Relaxed);
+    let (mut reader, writer) = stream.into_split();
+    let writer: Writer = Arc::new(AsyncMutex::new(writer));
+    // Hasta el Hello, como un daemon antiguo
+    let mut protocol_version = 1;
+
+    loop {
+        let Ok(payload) = proto::read_frame(&mut reader).await else {
+            return;
+        };
+        let request: IpcRequest = match proto::decode(&payload) {
+            Ok(request) => request,
+            Err(e) => {
+                let response = IpcResponse::Error {
+                    message: format!("Petición inválida: {}", e),
+                };
+                if !deliver(&writer, Reply::Response(response), protocol_version).await {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        match request {
+            // Cada petición con id se atiende en su propia tarea y responde cuando acaba
+            IpcRequest::Tagged { id, request } if protocol_version >= 4 && state.supports(CAP_MULTIPLEX) => {
+                state.requests.lock().unwrap().push((*request).clone());
+                let state = state.clone();
+                let writer = writer.clone();
+                tokio::spawn(async move {
+                    let reply = match answer(&state, *request).await {
+                        Reply::Response(response) => Reply::Response(IpcResponse::Tagged {
+                            id,
+                            response: Box::new(response),
+                        }),
+                        other => other,
+                    };
+                    deliver(&writer, reply, protocol_version).await;
+                });
+            }
+            request => {
+                state.requests.lock().unwrap().push(request.clone());
+                let subscribe = matches!(request, IpcRequest::Subscribe) && state.supports(CAP_SUBSCRIBE);
+                if matches!(request, IpcRequest::Hello { .. }) && state.protocol_version >= 2 {
+                    protocol_version = state.protocol_version;
+                }
+
+                let reply = answer(&state, request).await;
+                let subscribed = subscribe && matches!(reply, Reply::Response(_));
+                if !deliver(&writer, reply, protocol_version).await {
+                    return;
+                }
+                if subscribed {
+                    return push_changes(writer, state, protocol_version).await;
+                }
+            }
+        }
+    }
+}
+
+/// Respuesta a una petición aplicando el primer fallo que coincida
+async fn answer(state: &State, request: IpcRequest) -> Reply {
+    if let Some(fault) = state.take_fault(&request) {
+        if fault.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(fault.delay_ms)).await;
+        }
+        match fault.action {
+            FaultAction::Respond => {}
+            FaultAction::Error => {
+                return Reply::Response(IpcResponse::Error {
+                    message: fault.message.unwrap_or_else(|| "Error simulado".to_string()),
+                })
+            }
+            FaultAction::Disconnect => return Reply::Close,
+            FaultAction::Malformed => {
+                // Índice de variante inexistente
+                let mut bytes = 8u32.to_be_bytes().to_vec();
+                bytes.extend([0xff; 8]);
+                return Reply::Raw { bytes, close: false };
+            }
+            FaultAction::Truncated => {
+                let mut bytes = 64u32.to_be_bytes().to_vec();
+                bytes.extend([0; 8]);
+                return Reply::Raw { bytes, close: true };
+            }
+            FaultAction::Oversized => {
+                let bytes = ((MAX_FRAME_SIZE + 1) as u32).to_be_bytes().to_vec();
+                return Reply::Raw { bytes, close: true };
+            }
+            FaultAction::Silence => return Reply::Nothing,
+        }
+    }
+    Reply::Response(state.respond(request))
+}
+
+/// Envía la respuesta; false si la conexión queda cerrada
+async fn deliver(writer: &Writer, reply: Reply, protocol_version: u32) -> bool {
+    let mut writer = writer.lock().await;
+    match reply {
+        Reply::Response(response) => match encode_response(&response, protocol_version) {
+            Ok(payload) => proto::write_frame(&mut *writer, &payload).await.is_ok(),
+            Err(_) => false,
+        },
+        Reply::Raw { bytes, close } => {
+            let written = writer.write_all(&bytes).await.is_ok();
+            if close {
+                let _ = writer.shutdown().await;
+            }
+            written && !close
+        }
+        Reply::Close => {
+            let _ = writer.shutdown().await;
+            false
+        }
+        Reply::Nothing => true,
+    }
+}
+
+/// Conexión suscrita: eventos del fixture y cambios hechos por otras peticiones
+async fn push_changes(writer: Writer, state: Arc<State>, protocol_version: u32) {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    state.subscribers.lock().unwrap().push(sender.clone());
+
+    let events = state.events.clone();
+    tokio::spawn(async move {
+        let mut elapsed = 0;
+        for event in events {
+            tokio::time::sleep(Duration::from_millis(event.after_ms.saturating_sub(elapsed))).await;
+            elapsed = elapsed.max(event.after_ms);
+            if sender.send(event.paths).is_err() {
+                return;
+            }
+        }
+    });
+
+    while let Some(paths) = receiver.recv().await {
+        let response = IpcResponse::StatusChanged { paths };
+        if !deliver(&writer, Reply::Response(response), protocol_version).await {
+            return;
+        }
+    }
+}
+
+// ============================================================
+// Codificación para daemons antiguos
+// ============================================================
+
+/// FileStatusData de los daemons v1/v2
+#[derive(Serialize)]
+struct StatusV2 {
+    status: SyncStatus,
+    availability: FileAvailability,
+    is_shared: bool,
+}
+
+/// FileStatusData de los daemons v3
+#[derive(Serialize)]
+struct StatusV3 {
+    status: SyncStatus,
+    availability: FileAvailability,
+    is_shared: bool,
+    last_synced: Option<i64>,
+    owner: Option<String>,
+}
+
+/// Serializa la respuesta con el formato de `FileStatusData` de la versión negociada
+fn encode_response(response: &IpcResponse, protocol_version: u32) -> io::Result<Vec<u8>> {
+    match protocol_version {
+        v if v >= 4 => proto::encode(response),
+        3 => encode_legacy(response, |data| StatusV3 {
+            status: data.status,
+            availability: data.availability,
+            is_shared: data.is_shared,
+            last_synced: data.last_synced,
+            owner: data.owner.clone(),
+        }),
+        _ => encode_legacy(response, |data| StatusV2 {
+            status: data.status,
+            availability: data.availability,
+            is_shared: data.is_shared,
+        }),
+    }
+}
+
+fn encode_legacy<L: Serialize>(response: &IpcResponse, convert: impl Fn(&FileStatusData) -> L) -> io::Result<Vec<u8>> {
+    // bincode: índice de la variante (u32 little-endian) + contenido
+    let (index, body) = match response {
+        IpcResponse::ExtendedStatus(data) => (1u32, proto::encode(&convert(data))?),
+        IpcResponse::ExtendedStatusBatch(batch) => {
+            (7, proto::encode(&batch.iter().map(&convert).collect::<Vec<_>>())?)
+        }
+        IpcResponse::DirectoryStatus(entries) => (
+            8,
+            proto::encode(&entries.iter().map(|(path, data)| (path, convert(data))).collect::<Vec<_>>())?,
+        ),
+        other => return proto::encode(other),
+    };
+    let mut payload = index.to_le_bytes().to_vec();
+    payload.extend(body);
+    Ok(payload)
+}
+
+// ============================================================
+// Helpers
+// ============================================================
+
+/// Path local de una URI file:// (o el propio path)
+fn normalize(path: &str) -> String {
+    match path.strip_prefix("file://") {
+        Some(encoded) => percent_encoding::percent_decode_str(encoded)
+            .decode_utf8_lossy()
+            .into_owned(),
+        None => path.to_string(),
+    }
+}
+
+fn unknown_request() -> IpcResponse {
+    IpcResponse::Error {
+        message: "Unknown request".to_string(),
+    }
+}
+
+fn required_capability(request: &IpcRequest) -> Option<&'static str> {
+    match request {
+        IpcRequest::Subscribe => Some(CAP_SUBSCRIBE),
+        IpcRequest::GetDirectoryStatus { .. } => Some(CAP_DIRECTORY_STATUS),
+        IpcRequest::GetFileStatusBatch { .. } => Some(CAP_BATCH_STATUS),
+        IpcRequest::GetFileDetails { .. } => Some(CAP_FILE_DETAILS),
+        IpcRequest::GetMountPoints => Some(CAP_MOUNT_POINTS),
+        IpcRequest::ResolveConflict { .. } => Some(CAP_CONFLICTS),
+        IpcRequest::SetAvailability { .. } => Some(CAP_RECURSIVE_AVAILABILITY),
+        IpcRequest::GetFolderSummary { .. } => Some(CAP_FOLDER_SUMMARY),
+        IpcRequest::Tagged { .. } => Some(CAP_MULTIPLEX),
+        _ => None,
+    }
+}
+
+/// Nombre de la variante, tal como se escribe en `[[fault]] request`
+pub fn request_name(request: &IpcRequest) -> &'static str {
+    match request {
+        IpcRequest::GetFileStatus { .. } => "GetFileStatus",
+        IpcRequest::Ping => "Ping",
+        IpcRequest::SetOnlineOnly { .. } => "SetOnlineOnly",
+        IpcRequest::SetLocalOnline { .. } => "SetLocalOnline",
+        IpcRequest::GetFileAvailability { .. } => "GetFileAvailability",
+        IpcRequest::Subscribe => "Subscribe",
+        IpcRequest::GetDirectoryStatus { .. } => "GetDirectoryStatus",
+        IpcRequest::GetFileStatusBatch { .. } => "GetFileStatusBatch",
+        IpcRequest::Hello { .. } => "Hello",
+        IpcRequest::GetFileDetails { .. } => "GetFileDetails",
+        IpcRequest::SyncNow { .. } => "SyncNow",
+        IpcRequest::PauseSync { .. } => "PauseSync",
+        IpcRequest::GetMountPoints => "GetMountPoints",
+        IpcRequest::ResolveConflict { .. } => "ResolveConflict",
+        IpcRequest::RetrySync { .. } => "RetrySync",
+        IpcRequest::SetAvailability { .. } => "SetAvailability",
+        IpcRequest::GetFolderSummary { .. } => "GetFolderSummary",
+        IpcRequest::Tagged { .. } => "Tagged",
+    }
+}
+
+fn request_paths(request: &IpcRequest) -> Vec<&str> {
+    match request {
+        IpcRequest::GetFileStatus { path }
+        | IpcRequest::SetOnlineOnly { path }
+        | IpcRequest::SetLocalOnline { path }
+        | IpcRequest::GetFileAvailability { path }
+        | IpcRequest::GetDirectoryStatus { path }
+        | IpcRequest::GetFileDetails { path }
+        | IpcRequest::SyncNow { path }
+        | IpcRequest::PauseSync { path }
+        | IpcRequest::ResolveConflict { path, .. }
+        | IpcRequest::SetAvailability { path, .. }
+        | IpcRequest::GetFolderSummary { path } => vec![path.as_str()],
+        IpcRequest::GetFileStatusBatch { paths } | IpcRequest::RetrySync { paths } => {
+            paths.iter().map(String::as_str).collect()
+        }
+        IpcRequest::Tagged { request, .. } => request_paths(request),
+        IpcRequest::Ping | IpcRequest::Subscribe | IpcRequest::Hello { .. } | IpcRequest::GetMountPoints => {
+            Vec::new()
+        }
+    }
+}
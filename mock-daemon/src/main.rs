@@ -0,0 +1,67 @@
+//! `mock_daemon`: daemon de G-DriveXP simulado para probar la extensión sin Drive
+//!
+//! ```bash
+//! cargo run -p gdrivexp-mock-daemon -- mock-daemon/fixtures/ejemplo.toml
+//! GDRIVEXP_NAUTILUS_SOCKET=/tmp/gdrivexp-mock.sock nautilus
+//! ```
+
+use gdrivexp_mock_daemon::{Fixture, MockDaemon};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Uso: mock_daemon <fixture.toml> [--socket RUTA]";
+
+fn main() -> ExitCode {
+    let mut fixture_path = None;
+    let mut socket_path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--socket" => match args.next() {
+                Some(path) => socket_path = Some(PathBuf::from(path)),
+                None => return usage("--socket necesita una ruta"),
+            },
+            "-h" | "--help" => return usage(""),
+            _ if fixture_path.is_none() => fixture_path = Some(PathBuf::from(arg)),
+            _ => return usage(&format!("Argumento inesperado: {}", arg)),
+        }
+    }
+    let Some(fixture_path) = fixture_path else {
+        return usage("Falta el fixture");
+    };
+
+    let fixture = match Fixture::load(&fixture_path) {
+        Ok(fixture) => fixture,
+        Err(e) => {
+            eprintln!("Fixture inválido {}: {}", fixture_path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+
+    // Nunca el socket del daemon real salvo que se pida expresamente
+    let socket_path = socket_path.unwrap_or_else(|| {
+        let uid = unsafe { libc::getuid() };
+        std::env::temp_dir().join(format!("gdrivexp-mock-{}.sock", uid))
+    });
+
+    match MockDaemon::start_at(fixture, &socket_path) {
+        Ok(daemon) => {
+            println!("Daemon simulado escuchando en {}", socket_path.display());
+            println!("export GDRIVEXP_NAUTILUS_SOCKET={}", socket_path.display());
+            daemon.wait();
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("No se pudo escuchar en {}: {}", socket_path.display(), e);
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn usage(message: &str) -> ExitCode {
+    if !message.is_empty() {
+        eprintln!("{}\n", message);
+    }
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
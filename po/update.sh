@@ -0,0 +1,23 @@
+#!/bin/sh
+# Regenera la plantilla y fusiona los catálogos existentes.
+# Uso: po/update.sh (desde la raíz del proyecto)
+set -e
+xgettext --language=C --from-code=UTF-8 --keyword=tr --keyword=trn:1,2 \
+    --package-name=gdrivexp-nautilus --package-version=0.1.0 \
+    --add-comments=TRANSLATORS: \
+    --files-from=po/POTFILES --output=po/gdrivexp-nautilus.pot
+for lang in $(cat po/LINGUAS); do
+    msgmerge --quiet --update --backup=none "po/$lang.po" po/gdrivexp-nautilus.pot
+done
+
+# Todo texto debe estar traducido en cada catálogo
+status=0
+for lang in $(cat po/LINGUAS); do
+    missing=$(msgattrib --only-fuzzy --no-obsolete "po/$lang.po" | grep -c '^msgid "[^"]' || true)
+    missing=$((missing + $(msgattrib --untranslated --no-obsolete "po/$lang.po" | grep -c '^msgid "[^"]' || true)))
+    if [ "$missing" -gt 0 ]; then
+        echo "po/$lang.po: $missing textos sin traducir (o fuzzy)" >&2
+        status=1
+    fi
+done
+exit $status
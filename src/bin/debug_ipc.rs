@@ -1,83 +1,417 @@
-use std::env;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::UnixStream;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-
-#[derive(Debug, Clone, Serialize)]
-#[allow(dead_code)]
-enum IpcRequest {
-    GetFileStatus { path: String },
-    Ping,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-enum IpcResponse {
-    FileStatus(SyncStatus),
-    Pong,
-    Error { message: String },
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
-pub enum SyncStatus {
-    Synced,      // Verde: en local y en drive
-    CloudOnly,   // Azul: solo en drive
-    LocalOnly,   // Naranja: solo local (pending upload)
-    Error,       // Rojo: error de sincronización
-    Unknown,     // Sin emblema
-}
-
-fn main() -> std::io::Result<()> {
-    let rt = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()?;
-
-    rt.block_on(async {
-        let args: Vec<String> = env::args().collect();
-        if args.len() < 2 {
-            eprintln!("Uso: {} <file_uri_or_path>", args[0]);
-            eprintln!("Ejemplo: {} file:///home/alcss/GoogleDrive/archivo.txt", args[0]);
-            std::process::exit(1);
-        }
-
-        let input_path = &args[1];
-        println!("Consulta: {}", input_path);
-
-        let uid = unsafe { libc::getuid() };
-        let socket_path = PathBuf::from(format!("/run/user/{}/gdrivexp.sock", uid));
-        
-        println!("Conectando a socket: {:?}", socket_path);
-        let mut stream = UnixStream::connect(&socket_path).await?;
-        println!("Conectado.");
-
-        // Construir request
-        let request = IpcRequest::GetFileStatus {
-            path: input_path.to_string(),
+//! Cliente de línea de comandos para el daemon de G-DriveXP
+//!
+//! Usa el mismo `IpcClient` que la extensión (handshake, compatibilidad con
+//! daemons antiguos y reconexión incluidos). Códigos de salida: 0 si todo fue
+//! bien, 1 si el daemon devolvió un error o no responde, 2 si el uso es incorrecto.
+
+use gdrivexp_ipc_proto::{FileAvailability, FileStatusData};
+use gdrivexp_nautilus::ipc_client::IpcClient;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Entradas por lote en `tree` con daemons sin `GetDirectoryStatus` (igual que el worker de la extensión)
+const MAX_BATCH_SIZE: usize = 256;
+
+const USAGE: &str = "\
+Uso: debug_ipc [--json] [--socket RUTA] <comando> [argumentos]
+
+Comandos:
+  ping                          Comprueba el daemon y muestra protocolo y capacidades
+  status <ruta>...              Estado de sincronización de cada archivo
+  availability <ruta>...        Disponibilidad (local / solo en línea)
+  details <ruta>                Detalle completo (ID de Drive, revisión, tamaño...)
+  set-online-only <ruta>...     Libera espacio (deja sólo la copia en Drive)
+  set-local-online <ruta>...    Mantiene siempre una copia local
+  tree <directorio> [--max-depth N]
+                                Estado de todo un árbol, una consulta por directorio
+  mount-points                  Raíces de montaje del daemon
+  cache-stats                   Contadores de la caché de la extensión en Nautilus
+
+Sin --socket se usa el socket de la extensión: GDRIVEXP_NAUTILUS_SOCKET,
+ipc.socket_path de nautilus.toml o la ruta estándar.
+Las rutas pueden ser absolutas o URIs file://.";
+
+struct Options {
+    json: bool,
+    socket: Option<PathBuf>,
+    command: String,
+    args: Vec<String>,
+}
+
+/// Error de la orden: uso incorrecto (2) o fallo del daemon (1)
+enum Failure {
+    Usage(String),
+    Daemon,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("No se pudo crear el runtime: {}", e);
+            return ExitCode::from(1);
+        }
+    };
+
+    let client = match &options.socket {
+        Some(path) => IpcClient::with_socket_path(path.clone()),
+        None => {
+            let (client, errors) = IpcClient::from_user_config();
+            for error in errors {
+                eprintln!("Configuración: {}", error);
+            }
+            client
+        }
+    };
+
+    match rt.block_on(run(&client, &options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(Failure::Daemon) => ExitCode::from(1),
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut json = false;
+    let mut socket = None;
+    let mut rest = Vec::new();
+    let mut args = args.peekable();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--socket" => socket = Some(PathBuf::from(args.next().ok_or("--socket necesita una ruta")?)),
+            "-h" | "--help" => return Err(String::new()),
+            // Compatibilidad con la antigua opción
+            "--cache-stats" if rest.is_empty() => rest.push("cache-stats".to_string()),
+            _ => rest.push(arg),
+        }
+    }
+
+    if rest.is_empty() {
+        return Err("Falta el comando".to_string());
+    }
+    let command = rest.remove(0);
+    Ok(Options {
+        json,
+        socket,
+        command,
+        args: rest,
+    })
+}
+
+async fn run(client: &IpcClient, options: &Options) -> Result<(), Failure> {
+    let args = &options.args;
+    match options.command.as_str() {
+        "ping" => ping(client, options.json).await,
+        "status" => {
+            require_paths(args)?;
+            let mut ok = true;
+            let mut results = Vec::new();
+            for path in args {
+                let result = client.get_extended_status(path).await;
+                ok &= result.is_ok();
+                results.push(status_entry(path, result, options.json));
+            }
+            print_list(results, options.json);
+            ok.then_some(()).ok_or(Failure::Daemon)
+        }
+        "availability" => {
+            require_paths(args)?;
+            let mut ok = true;
+            let mut results = Vec::new();
+            for path in args {
+                let result = client.get_availability(path).await;
+                ok &= result.is_ok();
+                results.push(match result {
+                    Ok(availability) if options.json => json!({ "path": path, "availability": availability }),
+                    Ok(availability) => Value::String(format!("{}: {:?}", path, availability)),
+                    Err(e) => error_entry(path, &e, options.json),
+                });
+            }
+            print_list(results, options.json);
+            ok.then_some(()).ok_or(Failure::Daemon)
+        }
+        "details" => {
+            let [path] = args.as_slice() else {
+                return Err(Failure::Usage("details necesita exactamente una ruta".to_string()));
+            };
+            match client.get_file_details(path).await {
+                Ok(details) if options.json => {
+                    println!("{}", json!({ "path": path, "details": details }));
+                    Ok(())
+                }
+                Ok(details) => {
+                    println!("{}: {:#?}", path, details);
+                    Ok(())
+                }
+                Err(e) => fail(path, &e, options.json),
+            }
+        }
+        "set-online-only" | "set-local-online" => {
+            require_paths(args)?;
+            let online_only = options.command == "set-online-only";
+            let mut ok = true;
+            let mut results = Vec::new();
+            for path in args {
+                let result = if online_only {
+                    client.set_online_only(path).await
+                } else {
+                    client.set_local_online(path).await
+                };
+                let result = result.and_then(|applied| {
+                    applied
+                        .then_some(())
+                        .ok_or_else(|| io::Error::other("el daemon rechazó el cambio"))
+                });
+                ok &= result.is_ok();
+                results.push(match result {
+                    Ok(()) if options.json => json!({ "path": path, "ok": true }),
+                    Ok(()) => Value::String(format!("{}: OK", path)),
+                    Err(e) => error_entry(path, &e, options.json),
+                });
+            }
+            print_list(results, options.json);
+            ok.then_some(()).ok_or(Failure::Daemon)
+        }
+        "tree" => tree(client, args, options.json).await,
+        "mount-points" => match client.get_mount_points().await {
+            Ok(roots) if options.json => {
+                println!("{}", json!(roots));
+                Ok(())
+            }
+            Ok(roots) => {
+                roots.iter().for_each(|root| println!("{}", root));
+                Ok(())
+            }
+            Err(e) => fail("mount-points", &e, options.json),
+        },
+        "cache-stats" => cache_stats(options.json),
+        other => Err(Failure::Usage(format!("Comando desconocido: {}", other))),
+    }
+}
+
+async fn ping(client: &IpcClient, json: bool) -> Result<(), Failure> {
+    if let Err(e) = client.ping().await {
+        return fail("ping", &e, json);
+    }
+    let (version, capabilities) = client
+        .protocol_info()
+        .map(|info| (info.protocol_version, info.capabilities))
+        .unwrap_or((1, Vec::new()));
+    if json {
+        println!("{}", json!({ "ok": true, "protocol_version": version, "capabilities": capabilities }));
+    } else {
+        println!("Pong (protocolo v{})", version);
+        if !capabilities.is_empty() {
+            println!("Capacidades: {}", capabilities.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Recorre el árbol y consulta cada directorio con un solo `GetDirectoryStatus`
+/// (o por lotes / archivo a archivo con daemons que no lo soportan)
+async fn tree(client: &IpcClient, args: &[String], json: bool) -> Result<(), Failure> {
+    let (root, max_depth) = match args {
+        [root] => (root, usize::MAX),
+        [root, flag, depth] if flag == "--max-depth" => (
+            root,
+            depth
+                .parse()
+                .map_err(|_| Failure::Usage(format!("--max-depth inválido: {}", depth)))?,
+        ),
+        _ => return Err(Failure::Usage("tree necesita un directorio".to_string())),
+    };
+    let root = local_path(root);
+
+    let mut ok = true;
+    let mut results = Vec::new();
+    let mut pending = vec![(root, 0usize)];
+    while let Some((dir, depth)) = pending.pop() {
+        let mut entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                ok = false;
+                results.push(error_entry(&dir.to_string_lossy(), &e, json));
+                continue;
+            }
         };
-        
-        let request_bytes = bincode::serialize(&request)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        
-        // Enviar longitud + request
-        let len = (request_bytes.len() as u32).to_be_bytes();
-        stream.write_all(&len).await?;
-        stream.write_all(&request_bytes).await?;
-        
-        // Leer longitud
-        let mut len_buf = [0u8; 4];
-        stream.read_exact(&mut len_buf).await?;
-        let response_len = u32::from_be_bytes(len_buf) as usize;
-        
-        // Leer respuesta
-        let mut response_buf = vec![0u8; response_len];
-        stream.read_exact(&mut response_buf).await?;
-        
-        let response: IpcResponse = bincode::deserialize(&response_buf)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        
-        println!("Respuesta recibida: {:?}", response);
+        entries.sort();
 
+        let statuses = directory_statuses(client, &dir, &entries).await;
+        for (path, result) in entries.iter().zip(statuses) {
+            ok &= result.is_ok();
+            results.push(status_entry(&path.to_string_lossy(), result, json));
+        }
+
+        if depth < max_depth {
+            for entry in entries.iter().rev().filter(|p| p.is_dir() && !p.is_symlink()) {
+                pending.push((entry.clone(), depth + 1));
+            }
+        }
+    }
+
+    print_list(results, json);
+    ok.then_some(()).ok_or(Failure::Daemon)
+}
+
+/// Estado de `entries` (contenido de `dir`), en el mismo orden.
+///
+/// Las entradas que el daemon no incluye en `DirectoryStatus` no están rastreadas.
+async fn directory_statuses(client: &IpcClient, dir: &Path, entries: &[PathBuf]) -> Vec<io::Result<FileStatusData>> {
+    match client.get_directory_status(&dir.to_string_lossy()).await {
+        Ok(known) => {
+            // El daemon puede responder con rutas absolutas o con nombres
+            let mut known: HashMap<PathBuf, FileStatusData> = known
+                .into_iter()
+                .map(|(path, data)| (dir.join(local_path(&path)), data))
+                .collect();
+            return entries
+                .iter()
+                .map(|path| Ok(known.remove(path).unwrap_or_else(FileStatusData::unknown)))
+                .collect();
+        }
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+        Err(e) => return entries.iter().map(|_| Err(io::Error::new(e.kind(), e.to_string()))).collect(),
+    }
+
+    let mut statuses = Vec::with_capacity(entries.len());
+    for chunk in entries.chunks(MAX_BATCH_SIZE) {
+        let paths: Vec<String> = chunk.iter().map(|p| p.to_string_lossy().into_owned()).collect();
+        match client.get_extended_status_batch(&paths).await {
+            Ok(batch) => statuses.extend(batch.into_iter().map(Ok)),
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                for path in &paths {
+                    statuses.push(client.get_extended_status(path).await);
+                }
+            }
+            Err(e) => statuses.extend(paths.iter().map(|_| Err(io::Error::new(e.kind(), e.to_string())))),
+        }
+    }
+    statuses
+}
+
+fn cache_stats(json: bool) -> Result<(), Failure> {
+    let uid = unsafe { libc::getuid() };
+    let stats_path = PathBuf::from(format!("/run/user/{}/gdrivexp-nautilus-cache.stats", uid));
+    match std::fs::read_to_string(&stats_path) {
+        Ok(stats) if json => {
+            // Formato "clave=valor" por línea
+            let fields: serde_json::Map<String, Value> = stats
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| {
+                    let value = value.trim();
+                    let value = value.parse::<u64>().map_or_else(|_| json!(value), |n| json!(n));
+                    (key.trim().to_string(), value)
+                })
+                .collect();
+            println!("{}", Value::Object(fields));
+            Ok(())
+        }
+        Ok(stats) => {
+            print!("{}", stats);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("No se pudo leer {:?}: {} (¿Nautilus tiene la extensión cargada?)", stats_path, e);
+            Err(Failure::Daemon)
+        }
+    }
+}
+
+// === Helpers ===
+
+fn require_paths(args: &[String]) -> Result<(), Failure> {
+    if args.is_empty() {
+        Err(Failure::Usage("Falta al menos una ruta".to_string()))
+    } else {
         Ok(())
-    })
+    }
+}
+
+/// Ruta local de una URI file:// (o la propia ruta)
+fn local_path(input: &str) -> PathBuf {
+    match input.strip_prefix("file://") {
+        Some(encoded) => PathBuf::from(
+            percent_encoding::percent_decode_str(encoded)
+                .decode_utf8_lossy()
+                .into_owned(),
+        ),
+        None => Path::new(input).to_path_buf(),
+    }
+}
+
+fn status_entry(path: &str, result: io::Result<FileStatusData>, json: bool) -> Value {
+    match result {
+        Ok(data) if json => json!({ "path": path, "status": data }),
+        Ok(data) => Value::String(format!("{}: {}", path, describe(&data))),
+        Err(e) => error_entry(path, &e, json),
+    }
+}
+
+fn describe(data: &FileStatusData) -> String {
+    let mut text = format!("{:?}", data.status);
+    if data.availability != FileAvailability::NotTracked {
+        text.push_str(&format!(" ({:?})", data.availability));
+    }
+    if data.is_shared {
+        text.push_str(", compartido");
+    }
+    if let Some(owner) = &data.owner {
+        text.push_str(&format!(", propietario {}", owner));
+    }
+    if let Some(error) = &data.error_message {
+        text.push_str(&format!(", error: {}", error));
+    }
+    text
+}
+
+fn error_entry(path: &str, error: &io::Error, json: bool) -> Value {
+    if json {
+        json!({ "path": path, "error": error.to_string() })
+    } else {
+        Value::String(format!("{}: ERROR: {}", path, error))
+    }
+}
+
+fn fail(path: &str, error: &io::Error, json: bool) -> Result<(), Failure> {
+    match error_entry(path, error, json) {
+        Value::String(line) => eprintln!("{}", line),
+        value => println!("{}", value),
+    }
+    Err(Failure::Daemon)
+}
+
+/// Una línea por resultado, o un único array JSON
+fn print_list(results: Vec<Value>, json: bool) {
+    if json {
+        println!("{}", Value::Array(results));
+        return;
+    }
+    for result in results {
+        match result {
+            Value::String(line) if line.contains(": ERROR: ") => eprintln!("{}", line),
+            Value::String(line) => println!("{}", line),
+            other => println!("{}", other),
+        }
+    }
 }
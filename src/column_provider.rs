@@ -0,0 +1,124 @@
+//! Implementación del ColumnProvider: columnas de sincronización en la vista de lista
+//!
+//! Las columnas leen atributos de texto que `provider::update_file_info_impl`
+//! rellena junto con los emblemas, así Nautilus puede ordenar por ellos.
+
+use crate::ffi::*;
+use crate::i18n::{fill, tr};
+use crate::{FileStatusData, SyncStatus};
+use glib_sys::gpointer;
+use gobject_sys::GObject;
+
+/// Atributo con el estado de sincronización
+pub const ATTR_STATUS: &str = "gdrivexp_status";
+/// Atributo con la fecha de la última sincronización
+pub const ATTR_LAST_SYNCED: &str = "gdrivexp_last_synced";
+/// Atributo con el propietario en Drive
+pub const ATTR_OWNER: &str = "gdrivexp_owner";
+/// Atributo que indica si el archivo está compartido
+pub const ATTR_SHARED: &str = "gdrivexp_shared";
+
+/// Callback para get_columns
+pub unsafe extern "C" fn get_columns_impl(_provider: *mut GObject) -> *mut glib_sys::GList {
+    log_debug!("get_columns_impl called");
+
+    let mut columns: *mut glib_sys::GList = std::ptr::null_mut();
+
+    let column = create_column(
+        "GDriveXP::status_column",
+        ATTR_STATUS,
+        &tr("Drive status"),
+        &tr("Sync status with Google Drive"),
+    );
+    columns = g_list_append(columns, column as gpointer);
+
+    let column = create_column(
+        "GDriveXP::last_synced_column",
+        ATTR_LAST_SYNCED,
+        &tr("Last synced"),
+        &tr("Date of the last completed sync"),
+    );
+    columns = g_list_append(columns, column as gpointer);
+
+    let column = create_column(
+        "GDriveXP::owner_column",
+        ATTR_OWNER,
+        &tr("Owner"),
+        &tr("Owner of the file in Google Drive"),
+    );
+    columns = g_list_append(columns, column as gpointer);
+
+    let column = create_column(
+        "GDriveXP::shared_column",
+        ATTR_SHARED,
+        &tr("Shared"),
+        &tr("Whether the file is shared in Google Drive"),
+    );
+    columns = g_list_append(columns, column as gpointer);
+
+    columns
+}
+
+/// Rellena los atributos de columna de un archivo (main thread)
+pub unsafe fn apply_attributes(file: *mut NautilusFileInfo, data: &FileStatusData) {
+    add_string_attribute(file, ATTR_STATUS, &status_label(data.status));
+    let last_synced = data.last_synced.map(format_timestamp).unwrap_or_default();
+    add_string_attribute(file, ATTR_LAST_SYNCED, &last_synced);
+    add_string_attribute(file, ATTR_OWNER, data.owner.as_deref().unwrap_or(""));
+    add_string_attribute(file, ATTR_SHARED, &if data.is_shared { tr("Yes") } else { tr("No") });
+}
+
+/// Texto de la columna de estado; ordena de forma útil (pendientes y errores juntos)
+fn status_label(status: SyncStatus) -> String {
+    match status {
+        SyncStatus::Synced => tr("Synced"),
+        SyncStatus::CloudOnly => tr("Only in Drive"),
+        SyncStatus::LocalOnly => tr("Waiting to upload"),
+        SyncStatus::Error => tr("Error"),
+        SyncStatus::Conflict => tr("Conflict"),
+        SyncStatus::Partial => tr("Partially available"),
+        SyncStatus::Uploading { percent } => fill(&tr("Uploading ({}%)"), &[&percent]),
+        SyncStatus::Downloading { percent } => fill(&tr("Downloading ({}%)"), &[&percent]),
+        SyncStatus::Unknown => String::new(),
+    }
+}
+
+/// Fecha local en formato ordenable (AAAA-MM-DD HH:MM)
+pub fn format_timestamp(secs: i64) -> String {
+    unsafe {
+        let datetime = glib_sys::g_date_time_new_from_unix_local(secs);
+        if datetime.is_null() {
+            return String::new();
+        }
+        let format = str_to_cstring("%Y-%m-%d %H:%M");
+        let formatted = gchar_to_string_free(glib_sys::g_date_time_format(datetime, format.as_ptr()));
+        glib_sys::g_date_time_unref(datetime);
+
+        formatted.unwrap_or_default()
+    }
+}
+
+unsafe fn add_string_attribute(file: *mut NautilusFileInfo, name: &str, value: &str) {
+    let name_c = str_to_cstring(name);
+    let value_c = str_to_cstring(value);
+    nautilus_file_info_add_string_attribute(file, name_c.as_ptr(), value_c.as_ptr());
+}
+
+unsafe fn create_column(
+    name: &str,
+    attribute: &str,
+    label: &str,
+    description: &str,
+) -> *mut NautilusColumn {
+    let name_c = str_to_cstring(name);
+    let attribute_c = str_to_cstring(attribute);
+    let label_c = str_to_cstring(label);
+    let description_c = str_to_cstring(description);
+
+    nautilus_column_new(
+        name_c.as_ptr(),
+        attribute_c.as_ptr(),
+        label_c.as_ptr(),
+        description_c.as_ptr(),
+    )
+}
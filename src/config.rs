@@ -0,0 +1,738 @@
+//! Configuración de la extensión
+//!
+//! Se lee de `$XDG_CONFIG_HOME/gdrivexp/nautilus.toml` (o `~/.config/...`) en
+//! `nautilus_module_initialize`. Todo es opcional: los valores por defecto
+//! reproducen el comportamiento sin fichero. Las variables de entorno tienen
+//! prioridad sobre el fichero:
+//!
+//! | Variable | Clave |
+//! | :--- | :--- |
+//! | `GDRIVEXP_NAUTILUS_CONFIG` | (ruta del propio fichero) |
+//! | `GDRIVEXP_NAUTILUS_SOCKET` | `ipc.socket_path` |
+//! | `GDRIVEXP_NAUTILUS_QUERY_TIMEOUT_MS` | `ipc.query_timeout_ms` |
+//! | `GDRIVEXP_NAUTILUS_DAEMON_TIMEOUT_MS` | `ipc.daemon_timeout_ms` |
+//! | `GDRIVEXP_NAUTILUS_CACHE_TTL_SECS` | `cache.ttl_secs` |
+//! | `GDRIVEXP_NAUTILUS_CACHE_NEGATIVE_TTL_SECS` | `cache.negative_ttl_secs` |
+//! | `GDRIVEXP_NAUTILUS_CACHE_CAPACITY` | `cache.capacity` |
+//! | `GDRIVEXP_NAUTILUS_LOG` | `logging.level` |
+//! | `GDRIVEXP_NAUTILUS_LOG_FILE` | `logging.file` |
+//!
+//! El fichero se interpreta campo a campo: una clave desconocida o un valor
+//! inválido se ignora (ese campo conserva su valor por defecto, el resto del
+//! fichero se aplica) y se registra en el log junto con el motivo. Sólo un
+//! fichero que no es TOML válido se descarta entero.
+//!
+//! El fichero se vigila (ver `config_watch`): los cambios se aplican sin reiniciar
+//! Nautilus. La ruta del socket sólo se lee al crear cada `IpcClient`.
+
+use crate::logging::{LogFilter, LogOutput, LogSettings};
+use crate::status_cache::CacheSettings;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+/// Límite superior de los timeouts: más allá Nautilus parecería colgado
+const MAX_TIMEOUT_MS: u64 = 10_000;
+
+/// Límite superior del TTL de la caché: la suscripción puede caerse sin avisar
+const MAX_CACHE_TTL_SECS: u64 = 3_600;
+
+/// Límite superior de entradas en la caché de estados
+const MAX_CACHE_CAPACITY: u64 = 1_000_000;
+
+/// Tamaño mínimo del fichero de log antes de rotar
+const MIN_LOG_SIZE_KB: u64 = 16;
+
+/// Configuración efectiva (valores por defecto + fichero + entorno)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Socket del daemon; `None` usa la ruta estándar del protocolo
+    pub socket_path: Option<PathBuf>,
+    /// Espera máxima de las consultas síncronas desde el main thread (menús)
+    pub query_timeout: Duration,
+    /// Espera máxima de cada llamada del worker al daemon
+    pub daemon_timeout: Duration,
+    pub emblems: Emblems,
+    pub menu: MenuItems,
+    pub cache: CacheSettings,
+    pub logging: LogSettings,
+}
+
+/// Nombres de icono de cada emblema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emblems {
+    pub synced: String,
+    pub cloud_only: String,
+    pub local_only: String,
+    pub error: String,
+    pub conflict: String,
+    pub partial: String,
+    pub shared: String,
+    /// Se completa con `-0`, `-25`, `-50` o `-75`
+    pub uploading_prefix: String,
+    pub downloading_prefix: String,
+}
+
+/// Acciones de menú habilitadas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MenuItems {
+    pub free_space: bool,
+    pub keep_local: bool,
+    pub resolve_conflict: bool,
+    pub retry_sync: bool,
+    /// Acciones recursivas sobre carpetas (menú de archivo y de fondo)
+    pub folder_availability: bool,
+    pub sync_now: bool,
+    pub pause_sync: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            socket_path: None,
+            query_timeout: Duration::from_millis(50),
+            daemon_timeout: Duration::from_millis(200),
+            emblems: Emblems::default(),
+            menu: MenuItems::default(),
+            cache: CacheSettings::default(),
+            logging: LogSettings::default(),
+        }
+    }
+}
+
+impl Default for Emblems {
+    fn default() -> Self {
+        Self {
+            synced: "emblem-gdrivexp-synced".to_string(),
+            cloud_only: "emblem-gdrivexp-cloud".to_string(),
+            local_only: "emblem-gdrivexp-local".to_string(),
+            error: "emblem-gdrivexp-error".to_string(),
+            conflict: "emblem-gdrivexp-conflict".to_string(),
+            partial: "emblem-gdrivexp-partial".to_string(),
+            shared: "emblem-shared".to_string(),
+            uploading_prefix: "emblem-gdrivexp-upload".to_string(),
+            downloading_prefix: "emblem-gdrivexp-download".to_string(),
+        }
+    }
+}
+
+impl Default for MenuItems {
+    fn default() -> Self {
+        Self {
+            free_space: true,
+            keep_local: true,
+            resolve_conflict: true,
+            retry_sync: true,
+            folder_availability: true,
+            sync_now: true,
+            pause_sync: true,
+        }
+    }
+}
+
+impl Config {
+    /// Ruta del socket del daemon
+    pub fn socket_path(&self) -> PathBuf {
+        self.socket_path
+            .clone()
+            .unwrap_or_else(gdrivexp_ipc_proto::socket_path)
+    }
+}
+
+// ============================================================
+// Formato del fichero
+// ============================================================
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    ipc: FileIpc,
+    emblems: FileEmblems,
+    menu: FileMenu,
+    cache: FileCache,
+    logging: FileLogging,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileIpc {
+    socket_path: Option<PathBuf>,
+    query_timeout_ms: Option<u64>,
+    daemon_timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileEmblems {
+    synced: Option<String>,
+    cloud_only: Option<String>,
+    local_only: Option<String>,
+    error: Option<String>,
+    conflict: Option<String>,
+    partial: Option<String>,
+    shared: Option<String>,
+    uploading_prefix: Option<String>,
+    downloading_prefix: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileMenu {
+    free_space: Option<bool>,
+    keep_local: Option<bool>,
+    resolve_conflict: Option<bool>,
+    retry_sync: Option<bool>,
+    folder_availability: Option<bool>,
+    sync_now: Option<bool>,
+    pause_sync: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileCache {
+    ttl_secs: Option<u64>,
+    negative_ttl_secs: Option<u64>,
+    capacity: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileLogging {
+    level: Option<String>,
+    output: Option<String>,
+    file: Option<PathBuf>,
+    max_size_kb: Option<u64>,
+    keep: Option<u32>,
+}
+
+// ============================================================
+// Carga
+// ============================================================
+
+static CONFIG: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+fn slot() -> &'static RwLock<Arc<Config>> {
+    CONFIG.get_or_init(|| RwLock::new(Arc::new(Config::default())))
+}
+
+/// Configuración actual (la por defecto hasta que se llame a `load`)
+pub fn get() -> Arc<Config> {
+    slot()
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+/// Ruta del fichero de configuración
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("GDRIVEXP_NAUTILUS_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("gdrivexp").join("nautilus.toml"))
+}
+
+/// Lee fichero y entorno y sustituye la configuración global.
+///
+/// Devuelve la configuración anterior, para comparar qué cambió.
+pub fn load() -> Arc<Config> {
+    let (config, errors) = read();
+
+    // Los errores se registran después de configurar el log, para que lleguen a él
+    crate::logging::configure(&config.logging);
+    crate::status_cache::configure(&config.cache);
+    let previous = match slot().write() {
+        Ok(mut slot) => std::mem::replace(&mut *slot, Arc::new(config)),
+        Err(_) => return get(),
+    };
+    for error in &errors {
+        log_error!("CONFIG ERROR: {}", error);
+    }
+    log_info!("Config loaded: {:?}", get());
+    previous
+}
+
+/// Lee fichero y entorno sin instalar el resultado; devuelve también los errores
+pub fn read() -> (Config, Vec<String>) {
+    let mut errors = Vec::new();
+    let file = config_path().and_then(|path| read_file(&path, &mut errors));
+    let config = build(file.unwrap_or_default(), &env_overrides(), &mut errors);
+    (config, errors)
+}
+
+/// `None` si el fichero no existe o no se puede interpretar (se usan los valores por defecto)
+fn read_file(path: &Path, errors: &mut Vec<String>) -> Option<FileConfig> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            errors.push(format!("{}: {}", path.display(), e));
+            return None;
+        }
+    };
+    parse_file(&text, errors)
+        .map_err(|e| errors.push(format!("{}: {}", path.display(), e)))
+        .ok()
+}
+
+/// Interpreta el fichero campo a campo.
+///
+/// Las secciones y claves desconocidas o con un valor del tipo equivocado se
+/// registran en `errors` y se descartan; el resto se aplica. Sólo falla si el
+/// texto no es TOML válido.
+fn parse_file(text: &str, errors: &mut Vec<String>) -> Result<FileConfig, toml::de::Error> {
+    let table: toml::Table = text.parse()?;
+    let mut valid = toml::Table::new();
+    for (section, value) in table {
+        let toml::Value::Table(fields) = value else {
+            errors.push(format!("{}: debe ser una sección ([{}])", section, section));
+            continue;
+        };
+        let fields = match section.as_str() {
+            "ipc" => valid_fields::<FileIpc>(&section, fields, errors),
+            "emblems" => valid_fields::<FileEmblems>(&section, fields, errors),
+            "menu" => valid_fields::<FileMenu>(&section, fields, errors),
+            "cache" => valid_fields::<FileCache>(&section, fields, errors),
+            "logging" => valid_fields::<FileLogging>(&section, fields, errors),
+            _ => {
+                errors.push(format!("{}: sección desconocida", section));
+                continue;
+            }
+        };
+        valid.insert(section, toml::Value::Table(fields));
+    }
+    // Cada campo ya se comprobó por separado
+    Ok(toml::Value::Table(valid).try_into().unwrap_or_default())
+}
+
+/// Campos de `fields` que `T` acepta uno por uno
+fn valid_fields<T: DeserializeOwned>(section: &str, fields: toml::Table, errors: &mut Vec<String>) -> toml::Table {
+    fields
+        .into_iter()
+        .filter(|(key, value)| {
+            let single = toml::Table::from_iter([(key.clone(), value.clone())]);
+            match toml::Value::Table(single).try_into::<T>() {
+                Ok(_) => true,
+                Err(e) => {
+                    errors.push(format!("{}.{}: {}", section, key, e.message()));
+                    false
+                }
+            }
+        })
+        .collect()
+}
+
+/// Valores tomados de las variables de entorno
+#[derive(Debug, Default)]
+struct EnvOverrides {
+    socket_path: Option<String>,
+    query_timeout_ms: Option<String>,
+    daemon_timeout_ms: Option<String>,
+    cache_ttl_secs: Option<String>,
+    cache_negative_ttl_secs: Option<String>,
+    cache_capacity: Option<String>,
+    log_level: Option<String>,
+    log_file: Option<String>,
+}
+
+fn env_overrides() -> EnvOverrides {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    EnvOverrides {
+        socket_path: var("GDRIVEXP_NAUTILUS_SOCKET"),
+        query_timeout_ms: var("GDRIVEXP_NAUTILUS_QUERY_TIMEOUT_MS"),
+        daemon_timeout_ms: var("GDRIVEXP_NAUTILUS_DAEMON_TIMEOUT_MS"),
+        cache_ttl_secs: var("GDRIVEXP_NAUTILUS_CACHE_TTL_SECS"),
+        cache_negative_ttl_secs: var("GDRIVEXP_NAUTILUS_CACHE_NEGATIVE_TTL_SECS"),
+        cache_capacity: var("GDRIVEXP_NAUTILUS_CACHE_CAPACITY"),
+        log_level: var("GDRIVEXP_NAUTILUS_LOG"),
+        log_file: var("GDRIVEXP_NAUTILUS_LOG_FILE"),
+    }
+}
+
+/// Combina valores por defecto, fichero y entorno validando cada campo
+fn build(file: FileConfig, env: &EnvOverrides, errors: &mut Vec<String>) -> Config {
+    let mut config = Config::default();
+
+    let socket_path = env
+        .socket_path
+        .as_ref()
+        .map(PathBuf::from)
+        .or(file.ipc.socket_path);
+    if let Some(path) = socket_path {
+        match absolute_path("ipc.socket_path", path) {
+            Ok(path) => config.socket_path = Some(path),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let query_timeout = timeout_value("ipc.query_timeout_ms", &env.query_timeout_ms, file.ipc.query_timeout_ms, errors);
+    if let Some(timeout) = query_timeout {
+        config.query_timeout = timeout;
+    }
+    let daemon_timeout = timeout_value("ipc.daemon_timeout_ms", &env.daemon_timeout_ms, file.ipc.daemon_timeout_ms, errors);
+    if let Some(timeout) = daemon_timeout {
+        config.daemon_timeout = timeout;
+    }
+
+    let emblems = &mut config.emblems;
+    for (key, value, target) in [
+        ("emblems.synced", file.emblems.synced, &mut emblems.synced),
+        ("emblems.cloud_only", file.emblems.cloud_only, &mut emblems.cloud_only),
+        ("emblems.local_only", file.emblems.local_only, &mut emblems.local_only),
+        ("emblems.error", file.emblems.error, &mut emblems.error),
+        ("emblems.conflict", file.emblems.conflict, &mut emblems.conflict),
+        ("emblems.partial", file.emblems.partial, &mut emblems.partial),
+        ("emblems.shared", file.emblems.shared, &mut emblems.shared),
+        ("emblems.uploading_prefix", file.emblems.uploading_prefix, &mut emblems.uploading_prefix),
+        ("emblems.downloading_prefix", file.emblems.downloading_prefix, &mut emblems.downloading_prefix),
+    ] {
+        let Some(value) = value else { continue };
+        match icon_name(key, value) {
+            Ok(value) => *target = value,
+            Err(e) => errors.push(e),
+        }
+    }
+
+    let menu = &mut config.menu;
+    for (value, target) in [
+        (file.menu.free_space, &mut menu.free_space),
+        (file.menu.keep_local, &mut menu.keep_local),
+        (file.menu.resolve_conflict, &mut menu.resolve_conflict),
+        (file.menu.retry_sync, &mut menu.retry_sync),
+        (file.menu.folder_availability, &mut menu.folder_availability),
+        (file.menu.sync_now, &mut menu.sync_now),
+        (file.menu.pause_sync, &mut menu.pause_sync),
+    ] {
+        if let Some(value) = value {
+            *target = value;
+        }
+    }
+
+    // TTL 0: las respuestas no se reutilizan
+    let cache = &mut config.cache;
+    let ttl = bounded_number("cache.ttl_secs", &env.cache_ttl_secs, file.cache.ttl_secs, 0..=MAX_CACHE_TTL_SECS, "segundos", errors);
+    if let Some(secs) = ttl {
+        cache.ttl = Duration::from_secs(secs);
+    }
+    let negative_ttl = bounded_number(
+        "cache.negative_ttl_secs",
+        &env.cache_negative_ttl_secs,
+        file.cache.negative_ttl_secs,
+        0..=MAX_CACHE_TTL_SECS,
+        "segundos",
+        errors,
+    );
+    if let Some(secs) = negative_ttl {
+        cache.negative_ttl = Duration::from_secs(secs);
+    }
+    let capacity = bounded_number("cache.capacity", &env.cache_capacity, file.cache.capacity, 1..=MAX_CACHE_CAPACITY, "entradas", errors);
+    if let Some(capacity) = capacity {
+        cache.capacity = capacity as usize;
+    }
+
+    let logging = &mut config.logging;
+    if let Some(spec) = env.log_level.clone().or(file.logging.level) {
+        match LogFilter::parse(&spec) {
+            Ok(filter) => logging.filter = filter,
+            Err(e) => errors.push(format!("logging.level: {}", e)),
+        }
+    }
+    if let Some(output) = file.logging.output {
+        match LogOutput::parse(&output) {
+            Ok(output) => logging.output = output,
+            Err(e) => errors.push(format!("logging.output: {}", e)),
+        }
+    }
+    let log_file = env.log_file.as_ref().map(PathBuf::from).or(file.logging.file);
+    if let Some(path) = log_file {
+        match absolute_path("logging.file", path) {
+            Ok(path) => logging.file = Some(path),
+            Err(e) => errors.push(e),
+        }
+    }
+    if let Some(kb) = file.logging.max_size_kb {
+        if kb < MIN_LOG_SIZE_KB {
+            errors.push(format!("logging.max_size_kb: {} menor que {}", kb, MIN_LOG_SIZE_KB));
+        } else {
+            logging.max_size = kb * 1024;
+        }
+    }
+    if let Some(keep) = file.logging.keep {
+        logging.keep = keep;
+    }
+
+    config
+}
+
+fn timeout_value(
+    key: &str,
+    env: &Option<String>,
+    file: Option<u64>,
+    errors: &mut Vec<String>,
+) -> Option<Duration> {
+    bounded_number(key, env, file, 1..=MAX_TIMEOUT_MS, "milisegundos", errors).map(Duration::from_millis)
+}
+
+/// Número del entorno (o, si no es válido, del fichero) dentro de `range`
+fn bounded_number(
+    key: &str,
+    env: &Option<String>,
+    file: Option<u64>,
+    range: RangeInclusive<u64>,
+    unit: &str,
+    errors: &mut Vec<String>,
+) -> Option<u64> {
+    let value = match env {
+        Some(raw) => match raw.trim().parse::<u64>() {
+            Ok(value) => value,
+            Err(_) => {
+                errors.push(format!("{}: '{}' no es un número de {}", key, raw, unit));
+                file?
+            }
+        },
+        None => file?,
+    };
+
+    if !range.contains(&value) {
+        errors.push(format!(
+            "{}: {} fuera de rango ({}..={} {})",
+            key, value, range.start(), range.end(), unit
+        ));
+        return None;
+    }
+    Some(value)
+}
+
+fn absolute_path(key: &str, path: PathBuf) -> Result<PathBuf, String> {
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Err(format!("{}: '{}' debe ser una ruta absoluta", key, path.display()))
+    }
+}
+
+/// Nombres de icono del tema: no vacíos y sin separadores de ruta
+fn icon_name(key: &str, name: String) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() || trimmed.contains('/') || trimmed.contains(char::is_whitespace) {
+        Err(format!("{}: '{}' no es un nombre de icono válido", key, name))
+    } else {
+        Ok(trimmed.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_from(text: &str, env: &EnvOverrides) -> (Config, Vec<String>) {
+        let mut errors = Vec::new();
+        let file = parse_file(text, &mut errors).unwrap();
+        let config = build(file, env, &mut errors);
+        (config, errors)
+    }
+
+    #[test]
+    fn empty_file_gives_the_defaults() {
+        let (config, errors) = build_from("", &EnvOverrides::default());
+        assert_eq!(config, Config::default());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn file_values_are_applied() {
+        let text = r#"
+            [ipc]
+            socket_path = "/run/user/1000/gdrivexp.sock"
+            query_timeout_ms = 80
+
+            [emblems]
+            synced = " emblem-ok "
+
+            [menu]
+            pause_sync = false
+
+            [cache]
+            ttl_secs = 0
+            capacity = 10
+
+            [logging]
+            output = "file"
+            file = "/tmp/gdrivexp.log"
+            max_size_kb = 16
+            keep = 2
+        "#;
+        let (config, errors) = build_from(text, &EnvOverrides::default());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.socket_path(), PathBuf::from("/run/user/1000/gdrivexp.sock"));
+        assert_eq!(config.query_timeout, Duration::from_millis(80));
+        assert_eq!(config.emblems.synced, "emblem-ok");
+        assert!(!config.menu.pause_sync);
+        assert!(config.menu.sync_now);
+        assert_eq!(config.cache.ttl, Duration::ZERO);
+        assert_eq!(config.cache.capacity, 10);
+        assert_eq!(config.logging.file, Some(PathBuf::from("/tmp/gdrivexp.log")));
+        assert_eq!(config.logging.max_size, 16 * 1024);
+        assert_eq!(config.logging.keep, 2);
+    }
+
+    #[test]
+    fn env_overrides_the_file() {
+        let text = r#"
+            [ipc]
+            socket_path = "/run/file.sock"
+            daemon_timeout_ms = 300
+            [cache]
+            negative_ttl_secs = 5
+        "#;
+        let env = EnvOverrides {
+            socket_path: Some("/run/env.sock".to_string()),
+            daemon_timeout_ms: Some(" 400 ".to_string()),
+            cache_negative_ttl_secs: Some("1".to_string()),
+            ..EnvOverrides::default()
+        };
+        let (config, errors) = build_from(text, &env);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(config.socket_path, Some(PathBuf::from("/run/env.sock")));
+        assert_eq!(config.daemon_timeout, Duration::from_millis(400));
+        assert_eq!(config.cache.negative_ttl, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn invalid_env_number_falls_back_to_the_file() {
+        let env = EnvOverrides {
+            query_timeout_ms: Some("rápido".to_string()),
+            cache_capacity: Some("-1".to_string()),
+            ..EnvOverrides::default()
+        };
+        let (config, errors) = build_from("[ipc]\nquery_timeout_ms = 120", &env);
+        assert_eq!(config.query_timeout, Duration::from_millis(120));
+        assert_eq!(config.cache.capacity, CacheSettings::default().capacity);
+        assert_eq!(
+            errors,
+            [
+                "ipc.query_timeout_ms: 'rápido' no es un número de milisegundos",
+                "cache.capacity: '-1' no es un número de entradas",
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_numbers_keep_the_defaults() {
+        let text = r#"
+            [ipc]
+            query_timeout_ms = 0
+            daemon_timeout_ms = 10001
+            [cache]
+            ttl_secs = 3601
+            capacity = 0
+        "#;
+        let (config, errors) = build_from(text, &EnvOverrides::default());
+        assert_eq!(config, Config::default());
+        assert_eq!(
+            errors,
+            [
+                "ipc.query_timeout_ms: 0 fuera de rango (1..=10000 milisegundos)",
+                "ipc.daemon_timeout_ms: 10001 fuera de rango (1..=10000 milisegundos)",
+                "cache.ttl_secs: 3601 fuera de rango (0..=3600 segundos)",
+                "cache.capacity: 0 fuera de rango (1..=1000000 entradas)",
+            ]
+        );
+
+        let (config, _) = build_from("[ipc]\nquery_timeout_ms = 10000", &EnvOverrides::default());
+        assert_eq!(config.query_timeout, Duration::from_millis(10_000));
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_and_skipped() {
+        let (config, errors) = build_from("[ipc]\ntimeout_ms = 10", &EnvOverrides::default());
+        assert_eq!(config, Config::default());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("ipc.timeout_ms: unknown field `timeout_ms`"), "{:?}", errors);
+
+        let (config, errors) = build_from("verbose = true\n[colors]\nsynced = 'green'", &EnvOverrides::default());
+        assert_eq!(config, Config::default());
+        assert_eq!(errors, ["colors: sección desconocida", "verbose: debe ser una sección ([verbose])"]);
+    }
+
+    #[test]
+    fn a_bad_key_does_not_discard_its_neighbours() {
+        let text = r#"
+            [ipc]
+            query_timeout_ms = "rápido"
+            daemon_timeout_ms = 300
+            timeout_ms = 10
+
+            [menu]
+            pause_sync = "no"
+            sync_now = false
+
+            [logging]
+            keep = 3
+        "#;
+        let (config, errors) = build_from(text, &EnvOverrides::default());
+        assert_eq!(config.query_timeout, Config::default().query_timeout);
+        assert_eq!(config.daemon_timeout, Duration::from_millis(300));
+        assert!(config.menu.pause_sync);
+        assert!(!config.menu.sync_now);
+        assert_eq!(config.logging.keep, 3);
+
+        let keys: Vec<&str> = errors.iter().filter_map(|e| e.split(':').next()).collect();
+        assert_eq!(keys, ["ipc.query_timeout_ms", "ipc.timeout_ms", "menu.pause_sync"]);
+    }
+
+    #[test]
+    fn invalid_toml_discards_the_file() {
+        assert!(parse_file("[ipc\nquery_timeout_ms = 80", &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn relative_paths_are_rejected() {
+        let env = EnvOverrides {
+            log_file: Some("logs/nautilus.log".to_string()),
+            ..EnvOverrides::default()
+        };
+        let (config, errors) = build_from("[ipc]\nsocket_path = \"gdrivexp.sock\"", &env);
+        assert_eq!(config.socket_path, None);
+        assert_eq!(config.logging.file, None);
+        assert_eq!(
+            errors,
+            [
+                "ipc.socket_path: 'gdrivexp.sock' debe ser una ruta absoluta",
+                "logging.file: 'logs/nautilus.log' debe ser una ruta absoluta",
+            ]
+        );
+    }
+
+    #[test]
+    fn icon_names_reject_paths_and_whitespace() {
+        let text = r#"
+            [emblems]
+            synced = "/usr/share/icons/ok.png"
+            error = "emblem error"
+            shared = "   "
+            conflict = "emblem-conflict"
+        "#;
+        let (config, errors) = build_from(text, &EnvOverrides::default());
+        let defaults = Emblems::default();
+        assert_eq!(config.emblems.synced, defaults.synced);
+        assert_eq!(config.emblems.error, defaults.error);
+        assert_eq!(config.emblems.shared, defaults.shared);
+        assert_eq!(config.emblems.conflict, "emblem-conflict");
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("emblems.synced:"));
+    }
+
+    #[test]
+    fn small_log_rotation_size_is_rejected() {
+        let (config, errors) = build_from("[logging]\nmax_size_kb = 15", &EnvOverrides::default());
+        assert_eq!(config.logging.max_size, LogSettings::default().max_size);
+        assert_eq!(errors, ["logging.max_size_kb: 15 menor que 16"]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Recarga en caliente de `nautilus.toml`
+//!
+//! Un `GFileMonitor` sobre el fichero de configuración (exista o no) vuelve a
+//! cargarlo cuando cambia. Los timeouts, las acciones de menú y el log se leen
+//! de `config::get()` en cada uso, así que basta con sustituir la configuración;
+//! si cambian los emblemas hay que pedir a Nautilus que vuelva a consultar los
+//! archivos que ya los muestran.
+
+use crate::config;
+use crate::ffi::*;
+use gio_sys::{GFile, GFileMonitor, GFileMonitorEvent};
+use glib_sys::gpointer;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Espera tras el último evento antes de releer (los editores guardan en varios pasos)
+const RELOAD_DELAY_MS: u32 = 200;
+
+/// Monitor vivo durante toda la sesión (sólo se accede desde el main loop)
+static mut MONITOR: *mut GFileMonitor = std::ptr::null_mut();
+
+/// Hay una recarga programada
+static RELOAD_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Empieza a vigilar el fichero de configuración (main thread)
+pub unsafe fn start() {
+    if !(*std::ptr::addr_of!(MONITOR)).is_null() {
+        return;
+    }
+    let Some(path) = config::config_path() else {
+        log_info!("Config watch: no config path (HOME unset)");
+        return;
+    };
+
+    let path_c = str_to_cstring(&path.to_string_lossy());
+    let file = gio_sys::g_file_new_for_path(path_c.as_ptr());
+    let mut error: *mut glib_sys::GError = std::ptr::null_mut();
+    let monitor = gio_sys::g_file_monitor_file(
+        file,
+        gio_sys::G_FILE_MONITOR_WATCH_MOVES,
+        std::ptr::null_mut(),
+        &mut error,
+    );
+    gobject_sys::g_object_unref(file as *mut gobject_sys::GObject);
+
+    if monitor.is_null() {
+        let message = if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            let message = std::ffi::CStr::from_ptr((*error).message).to_string_lossy().into_owned();
+            glib_sys::g_error_free(error);
+            message
+        };
+        log_warn!("Config watch failed for {}: {}", path.display(), message);
+        return;
+    }
+
+    type ChangedHandler = unsafe extern "C" fn(*mut GFileMonitor, *mut GFile, *mut GFile, GFileMonitorEvent, gpointer);
+    let handler: ChangedHandler = file_changed;
+    let signal = str_to_cstring("changed");
+    g_signal_connect_data(
+        monitor as gpointer,
+        signal.as_ptr(),
+        Some(std::mem::transmute::<ChangedHandler, unsafe extern "C" fn()>(handler)),
+        std::ptr::null_mut(),
+        None,
+        0, // G_CONNECT_DEFAULT
+    );
+
+    MONITOR = monitor;
+    log_info!("Watching config file {}", path.display());
+}
+
+unsafe extern "C" fn file_changed(
+    _monitor: *mut GFileMonitor,
+    _file: *mut GFile,
+    _other_file: *mut GFile,
+    event: GFileMonitorEvent,
+    _data: gpointer,
+) {
+    // CHANGED llega varias veces por escritura: se espera a CHANGES_DONE_HINT
+    if event == gio_sys::G_FILE_MONITOR_EVENT_CHANGED
+        || event == gio_sys::G_FILE_MONITOR_EVENT_ATTRIBUTE_CHANGED
+    {
+        return;
+    }
+    if !RELOAD_PENDING.swap(true, Ordering::AcqRel) {
+        glib_sys::g_timeout_add(RELOAD_DELAY_MS, Some(reload_timeout), std::ptr::null_mut());
+    }
+}
+
+unsafe extern "C" fn reload_timeout(_data: gpointer) -> glib_sys::gboolean {
+    RELOAD_PENDING.store(false, Ordering::Release);
+
+    let previous = config::load();
+    let current = config::get();
+    if previous.emblems != current.emblems {
+        // Los estados en caché siguen siendo válidos: sólo cambia cómo se pintan
+        let uris = crate::status_cache::cached_uris();
+        log_debug!("Emblem mapping changed, refreshing {} files", uris.len());
+        crate::subscription::invalidate_file_infos(&uris);
+    }
+
+    glib_sys::G_SOURCE_REMOVE
+}
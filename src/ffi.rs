@@ -29,6 +29,30 @@ pub struct NautilusMenuItem {
     _private: [u8; 0],
 }
 
+/// Opaco: submenú de un item de menú contextual
+#[repr(C)]
+pub struct NautilusMenu {
+    _private: [u8; 0],
+}
+
+/// Opaco: representa una columna de la vista de lista
+#[repr(C)]
+pub struct NautilusColumn {
+    _private: [u8; 0],
+}
+
+/// Opaco: sección del diálogo de Propiedades
+#[repr(C)]
+pub struct NautilusPropertiesModel {
+    _private: [u8; 0],
+}
+
+/// Opaco: fila nombre/valor de una sección de Propiedades
+#[repr(C)]
+pub struct NautilusPropertiesItem {
+    _private: [u8; 0],
+}
+
 // ============================================================
 // Enums
 // ============================================================
@@ -93,6 +117,37 @@ pub struct NautilusMenuProviderInterface {
     >,
 }
 
+// ============================================================
+// Interface: NautilusColumnProvider
+// ============================================================
+
+/// VTable para NautilusColumnProvider interface
+#[repr(C)]
+pub struct NautilusColumnProviderInterface {
+    pub g_iface: GTypeInterface,
+    
+    pub get_columns: Option<
+        unsafe extern "C" fn(provider: *mut GObject) -> *mut glib_sys::GList,
+    >,
+}
+
+// ============================================================
+// Interface: NautilusPropertiesModelProvider
+// ============================================================
+
+/// VTable para NautilusPropertiesModelProvider interface
+#[repr(C)]
+pub struct NautilusPropertiesModelProviderInterface {
+    pub g_iface: GTypeInterface,
+    
+    pub get_models: Option<
+        unsafe extern "C" fn(
+            provider: *mut GObject,
+            files: *mut glib_sys::GList,
+        ) -> *mut glib_sys::GList,
+    >,
+}
+
 // ============================================================
 // Funciones externas de libnautilus-extension
 // ============================================================
@@ -102,12 +157,66 @@ extern "C" {
     // Funciones de NautilusFileInfo
     pub fn nautilus_file_info_get_uri(file_info: *mut NautilusFileInfo) -> *mut c_char;
     pub fn nautilus_file_info_add_emblem(file_info: *mut NautilusFileInfo, emblem_name: *const c_char);
-    #[allow(dead_code)]
     pub fn nautilus_file_info_is_directory(file_info: *mut NautilusFileInfo) -> glib_sys::gboolean;
     
+    // Devuelve el NautilusFileInfo ya cargado para una URI (nueva referencia) o NULL
+    pub fn nautilus_file_info_lookup_for_uri(uri: *const c_char) -> *mut NautilusFileInfo;
+    
+    // Fuerza a Nautilus a volver a llamar a update_file_info para ese archivo
+    pub fn nautilus_file_info_invalidate_extension_info(file_info: *mut NautilusFileInfo);
+    
     // Obtener el GType de NautilusInfoProvider
     pub fn nautilus_info_provider_get_type() -> GType;
     
+    // Notificar a Nautilus que terminó una actualización asíncrona (InProgress)
+    pub fn nautilus_info_provider_update_complete_invoke(
+        update_complete: *mut GClosure,
+        provider: *mut GObject,
+        handle: *mut NautilusOperationHandle,
+        result: NautilusOperationResult,
+    );
+    
+    // Submenús
+    pub fn nautilus_menu_new() -> *mut NautilusMenu;
+    pub fn nautilus_menu_append_item(menu: *mut NautilusMenu, item: *mut NautilusMenuItem);
+    pub fn nautilus_menu_item_set_submenu(item: *mut NautilusMenuItem, menu: *mut NautilusMenu);
+    
+    // Atributo de texto que muestran las columnas de la vista de lista
+    pub fn nautilus_file_info_add_string_attribute(
+        file_info: *mut NautilusFileInfo,
+        attribute_name: *const c_char,
+        value: *const c_char,
+    );
+    
+    // Obtener el GType de NautilusColumnProvider
+    pub fn nautilus_column_provider_get_type() -> GType;
+    
+    // Crear una nueva columna
+    pub fn nautilus_column_new(
+        name: *const c_char,
+        attribute: *const c_char,
+        label: *const c_char,
+        description: *const c_char,
+    ) -> *mut NautilusColumn;
+    
+    // Obtener el GType de NautilusPropertiesModelProvider
+    pub fn nautilus_properties_model_provider_get_type() -> GType;
+    
+    // GType de NautilusPropertiesItem (tipo de los elementos del GListStore)
+    pub fn nautilus_properties_item_get_type() -> GType;
+    
+    // Crear una sección de Propiedades (toma posesión de `model`)
+    pub fn nautilus_properties_model_new(
+        title: *const c_char,
+        model: *mut gio_sys::GListModel,
+    ) -> *mut NautilusPropertiesModel;
+    
+    // Crear una fila nombre/valor
+    pub fn nautilus_properties_item_new(
+        name: *const c_char,
+        value: *const c_char,
+    ) -> *mut NautilusPropertiesItem;
+    
     // Obtener el GType de NautilusMenuProvider
     pub fn nautilus_menu_provider_get_type() -> GType;
     
@@ -172,6 +281,16 @@ extern "C" {
     pub fn g_list_length(list: *mut glib_sys::GList) -> u32;
 }
 
+// ============================================================
+// gettext (glibc)
+// ============================================================
+
+extern "C" {
+    pub fn bindtextdomain(domainname: *const c_char, dirname: *const c_char) -> *mut c_char;
+
+    pub fn bind_textdomain_codeset(domainname: *const c_char, codeset: *const c_char) -> *mut c_char;
+}
+
 // ============================================================
 // Macros de utilidad
 // ============================================================
@@ -186,7 +305,34 @@ pub unsafe fn gchar_to_string_free(ptr: *mut c_char) -> Option<String> {
     Some(s)
 }
 
+/// Copia un *const c_char que no nos pertenece (no se libera)
+pub unsafe fn cstr_to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
 /// Convierte un &str a *const c_char (temporal, no usar fuera del scope)
+///
+/// Los textos pueden venir del daemon (mensajes de error, propietario...):
+/// un NUL interior se sustituye por U+FFFD en vez de abortar dentro de un
+/// callback `extern "C"`.
 pub fn str_to_cstring(s: &str) -> std::ffi::CString {
-    std::ffi::CString::new(s).expect("CString conversion failed")
+    match std::ffi::CString::new(s) {
+        Ok(cstring) => cstring,
+        Err(_) => std::ffi::CString::new(s.replace('\0', "\u{FFFD}")).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn str_to_cstring_replaces_interior_nul() {
+        assert_eq!(str_to_cstring("Cuota excedida").to_str().unwrap(), "Cuota excedida");
+        assert_eq!(str_to_cstring("a\0b\0").to_str().unwrap(), "a\u{FFFD}b\u{FFFD}");
+        assert_eq!(str_to_cstring("").as_bytes(), b"");
+    }
 }
@@ -0,0 +1,166 @@
+//! Emblemas agregados de carpetas
+//!
+//! El estado propio de una carpeta dice poco de su contenido: una carpeta
+//! "sincronizada" puede contener archivos con error o solo en Drive. Para las
+//! carpetas rastreadas se pide al daemon un `FolderSummary` recursivo y el
+//! emblema pasa a reflejar el estado agregado (ver `FolderSummary::aggregate_status`).
+//!
+//! Los resúmenes se guardan en una caché propia con TTL; cuando el daemon
+//! notifica un cambio se desalojan todas las carpetas antecesoras. Junto a
+//! ellos se guarda el alcance de las acciones recursivas (`FolderImpact`) que
+//! muestra el menú de la carpeta, con la misma vida y el mismo desalojo.
+
+use crate::mount_points;
+use gdrivexp_ipc_proto::{AvailabilityImpact, FolderSummary};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Vida de un resumen en caché
+const SUMMARY_TTL: Duration = Duration::from_secs(30);
+
+/// Número máximo de carpetas en caché (al superarlo se vacía entera)
+const MAX_ENTRIES: usize = 2_000;
+
+type Cache<T> = Mutex<Option<HashMap<String, (Instant, T)>>>;
+
+static CACHE: Cache<FolderSummary> = Mutex::new(None);
+
+static IMPACTS: Cache<FolderImpact> = Mutex::new(None);
+
+/// Alcance de descargar / liberar una carpeta entera, según la simulación del daemon.
+///
+/// `None` si el daemon no admite acciones recursivas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FolderImpact {
+    pub download: Option<AvailabilityImpact>,
+    pub free: Option<AvailabilityImpact>,
+}
+
+fn cached<T: Copy>(cache: &Cache<T>, uri: &str) -> Option<T> {
+    let mut guard = cache.lock().ok()?;
+    let cache = guard.get_or_insert_with(HashMap::new);
+    match cache.get(uri) {
+        Some((expires_at, value)) if *expires_at > Instant::now() => Some(*value),
+        Some(_) => {
+            cache.remove(uri);
+            None
+        }
+        None => None,
+    }
+}
+
+fn insert<T>(cache: &Cache<T>, uri: &str, value: T) {
+    let Ok(mut guard) = cache.lock() else {
+        return;
+    };
+    let cache = guard.get_or_insert_with(HashMap::new);
+    if cache.len() >= MAX_ENTRIES && !cache.contains_key(uri) {
+        cache.clear();
+    }
+    cache.insert(uri.to_string(), (Instant::now() + SUMMARY_TTL, value));
+}
+
+/// Resumen en caché de una carpeta, si no ha expirado
+pub fn lookup(uri: &str) -> Option<FolderSummary> {
+    cached(&CACHE, uri)
+}
+
+pub fn store(uri: &str, summary: FolderSummary) {
+    insert(&CACHE, uri, summary);
+}
+
+/// Alcance en caché de las acciones recursivas sobre una carpeta, si no ha expirado
+pub fn lookup_impact(uri: &str) -> Option<FolderImpact> {
+    cached(&IMPACTS, uri)
+}
+
+pub fn store_impact(uri: &str, impact: FolderImpact) {
+    insert(&IMPACTS, uri, impact);
+}
+
+/// Sustituye el estado de la carpeta por el agregado de su contenido.
+///
+/// Las carpetas no rastreadas (o vacías) conservan su estado propio.
+pub fn apply(data: &mut crate::FileStatusData, summary: &FolderSummary) {
+    if data.status == crate::SyncStatus::Unknown {
+        return;
+    }
+    if let Some(status) = summary.aggregate_status() {
+        data.status = status;
+    }
+}
+
+/// Desaloja los resúmenes de las carpetas que contienen las URIs cambiadas.
+///
+/// Devuelve esas carpetas (sin repetir y sin incluir las propias URIs) para
+/// que se refresque también su emblema: las que estaban en caché y las que
+/// quedan dentro de un punto de montaje conocido.
+pub fn invalidate_ancestors(uris: &[String]) -> Vec<String> {
+    // El alcance cambia con cualquier archivo del contenido, sin que importe el emblema
+    if let Some(impacts) = IMPACTS.lock().ok().as_mut().and_then(|g| g.as_mut()) {
+        for uri in uris {
+            let mut current = uri.as_str();
+            while current.len() > "file://".len() {
+                impacts.remove(current);
+                let parent = crate::logic::parent_uri(current);
+                if parent == current {
+                    break;
+                }
+                current = parent;
+            }
+        }
+    }
+
+    let mut guard = CACHE.lock().ok();
+    let mut cache = guard.as_mut().and_then(|g| g.as_mut());
+
+    let mut ancestors = BTreeSet::new();
+    for uri in uris {
+        // La propia URI puede ser una carpeta
+        if let Some(c) = cache.as_mut() {
+            c.remove(uri.as_str());
+        }
+        let mut current = crate::logic::parent_uri(uri);
+        while current.len() > "file://".len() {
+            let cached = cache
+                .as_mut()
+                .is_some_and(|c| c.remove(current).is_some());
+            if cached || mount_points::is_inside(current) {
+                ancestors.insert(current.to_string());
+            }
+            let parent = crate::logic::parent_uri(current);
+            if parent == current {
+                break;
+            }
+            current = parent;
+        }
+    }
+
+    ancestors
+        .into_iter()
+        .filter(|a| !uris.contains(a))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_evict_the_impact_of_every_ancestor() {
+        let folder = "file:///srv/gdrivexp-impact/Drive/Fotos";
+        let other = "file:///srv/gdrivexp-impact/Drive/Docs";
+        let impact = FolderImpact {
+            download: Some(AvailabilityImpact { files: 2, bytes: 2048 }),
+            free: None,
+        };
+        store_impact(folder, impact);
+        store_impact(other, impact);
+        assert_eq!(lookup_impact(folder), Some(impact));
+
+        invalidate_ancestors(&[format!("{}/2024/playa.jpg", folder)]);
+        assert_eq!(lookup_impact(folder), None);
+        assert_eq!(lookup_impact(other), Some(impact));
+    }
+}
@@ -0,0 +1,283 @@
+//! Traducción de los textos visibles con gettext
+//!
+//! Los textos del código están en inglés (msgid) y se traducen con el catálogo
+//! `gdrivexp-nautilus.mo` del locale de la sesión (el que Nautilus ya fijó con
+//! `setlocale`). Sin catálogo para el idioma se muestran en inglés.
+//!
+//! Los marcadores `{}` de los textos traducidos se rellenan con `fill`, en orden.
+
+use crate::ffi::*;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+/// Dominio gettext de la extensión
+pub const DOMAIN: &str = "gdrivexp-nautilus";
+
+static INIT: Once = Once::new();
+
+/// Enlaza el dominio con su directorio de catálogos (una sola vez)
+pub fn init() {
+    INIT.call_once(|| {
+        let dir = locale_dir();
+        log_info!("Translations from {}", dir.display());
+        let domain = str_to_cstring(DOMAIN);
+        let dir_c = str_to_cstring(&dir.to_string_lossy());
+        let codeset = str_to_cstring("UTF-8");
+        unsafe {
+            bindtextdomain(domain.as_ptr(), dir_c.as_ptr());
+            bind_textdomain_codeset(domain.as_ptr(), codeset.as_ptr());
+        }
+    });
+}
+
+/// `GDRIVEXP_NAUTILUS_LOCALEDIR`, la instalación de usuario
+/// (`~/.local/share/locale`) si tiene algún catálogo nuestro, o la del sistema
+fn locale_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("GDRIVEXP_NAUTILUS_LOCALEDIR") {
+        return PathBuf::from(dir);
+    }
+    let user_dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("locale")),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/locale")),
+    };
+    match user_dir {
+        Some(dir) if has_catalog(&dir) => dir,
+        _ => PathBuf::from(option_env!("GDRIVEXP_LOCALEDIR").unwrap_or("/usr/share/locale")),
+    }
+}
+
+fn has_catalog(dir: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let catalog = format!("LC_MESSAGES/{}.mo", DOMAIN);
+    entries
+        .flatten()
+        .any(|entry| entry.path().join(&catalog).is_file())
+}
+
+/// Texto traducido
+pub fn tr(msgid: &str) -> String {
+    init();
+    let domain = str_to_cstring(DOMAIN);
+    let msgid_c = str_to_cstring(msgid);
+    unsafe { cstr_to_string(glib_sys::g_dgettext(domain.as_ptr(), msgid_c.as_ptr())) }
+        .unwrap_or_else(|| msgid.to_string())
+}
+
+/// Texto traducido con plural según `n`
+pub fn trn(singular: &str, plural: &str, n: u64) -> String {
+    init();
+    let domain = str_to_cstring(DOMAIN);
+    let singular_c = str_to_cstring(singular);
+    let plural_c = str_to_cstring(plural);
+    let n = std::os::raw::c_ulong::try_from(n).unwrap_or(std::os::raw::c_ulong::MAX);
+    unsafe {
+        cstr_to_string(glib_sys::g_dngettext(
+            domain.as_ptr(),
+            singular_c.as_ptr(),
+            plural_c.as_ptr(),
+            n,
+        ))
+    }
+    .unwrap_or_else(|| if n == 1 { singular } else { plural }.to_string())
+}
+
+/// Sustituye cada `{}` de `template` por el siguiente argumento
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        result.push_str(&rest[..pos]);
+        match args.next() {
+            Some(arg) => result.push_str(&arg.to_string()),
+            None => result.push_str("{}"),
+        }
+        rest = &rest[pos + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn fill_replaces_placeholders_in_order() {
+        assert_eq!(fill("{} de {}", &[&3, &"10"]), "3 de 10");
+        assert_eq!(fill("Sin marcadores", &[]), "Sin marcadores");
+        assert_eq!(fill("{}{}", &[&"a", &"b"]), "ab");
+    }
+
+    #[test]
+    fn fill_tolerates_placeholder_count_mismatches() {
+        // Una traducción con menos argumentos de los que espera deja los marcadores visibles
+        assert_eq!(fill("Error: {} ({})", &[&"cuota"]), "Error: cuota ({})");
+        // Los argumentos sobrantes se ignoran
+        assert_eq!(fill("Error: {}", &[&"cuota", &42]), "Error: cuota");
+        // Un argumento con "{}" no se vuelve a rellenar
+        assert_eq!(fill("{} y {}", &[&"{}", &"b"]), "{} y b");
+    }
+
+    /// Mensaje extraído del código: msgid y, para `trn`, msgid_plural
+    type Message = (String, Option<String>);
+
+    /// Entrada de un catálogo .po
+    #[derive(Debug, Default)]
+    struct PoEntry {
+        fuzzy: bool,
+        msgid: String,
+        msgid_plural: Option<String>,
+        msgstr: Vec<String>,
+    }
+
+    /// Literal de cadena al comienzo de `text` (tras espacios); devuelve el valor y el resto
+    fn string_literal(text: &str) -> Option<(String, &str)> {
+        let text = text.trim_start();
+        let mut chars = text.strip_prefix('"')?.char_indices();
+        let mut value = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &text[i + 2..])),
+                '\\' => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    /// Llamadas `tr("...")` y `trn("...", "...", n)` con literales, como las extrae xgettext
+    fn extract_messages(source: &str) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for (pos, _) in source.match_indices("tr") {
+            let before = source[..pos].chars().next_back();
+            if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let rest = &source[pos + 2..];
+            if let Some(args) = rest.strip_prefix('(') {
+                if let Some((msgid, _)) = string_literal(args) {
+                    messages.push((msgid, None));
+                }
+            } else if let Some(args) = rest.strip_prefix("n(") {
+                let Some((singular, args)) = string_literal(args) else {
+                    continue;
+                };
+                let Some(args) = args.trim_start().strip_prefix(',') else {
+                    continue;
+                };
+                if let Some((plural, _)) = string_literal(args) {
+                    messages.push((singular, Some(plural)));
+                }
+            }
+        }
+        messages
+    }
+
+    fn parse_po(text: &str) -> HashMap<String, PoEntry> {
+        let mut entries = HashMap::new();
+        for block in text.split("\n\n") {
+            let mut entry = PoEntry::default();
+            // Cadena que continúan las líneas que empiezan por comillas
+            let mut current: Option<&mut String> = None;
+            for line in block.lines() {
+                if line.starts_with("#,") {
+                    entry.fuzzy |= line.contains("fuzzy");
+                    continue;
+                }
+                if line.starts_with('#') {
+                    continue;
+                }
+                let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+                let value = string_literal(rest).map(|(value, _)| value).unwrap_or_default();
+                current = match keyword {
+                    "msgid" => {
+                        entry.msgid = value;
+                        Some(&mut entry.msgid)
+                    }
+                    "msgid_plural" => Some(entry.msgid_plural.insert(value)),
+                    k if k == "msgstr" || k.starts_with("msgstr[") => {
+                        entry.msgstr.push(value);
+                        entry.msgstr.last_mut()
+                    }
+                    _ => {
+                        let continuation = string_literal(line).map(|(value, _)| value);
+                        if let (Some(target), Some(value)) = (current.as_deref_mut(), continuation) {
+                            target.push_str(&value);
+                        }
+                        current
+                    }
+                };
+            }
+            if !entry.msgid.is_empty() {
+                entries.insert(entry.msgid.clone(), entry);
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn every_message_has_a_spanish_translation() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let catalog = parse_po(&std::fs::read_to_string(root.join("po/es.po")).unwrap());
+
+        let mut messages = Vec::new();
+        for file in include_str!("../po/POTFILES").lines().filter(|l| !l.trim().is_empty()) {
+            let source = std::fs::read_to_string(root.join(file.trim())).unwrap();
+            messages.extend(extract_messages(&source));
+        }
+        assert!(messages.len() > 20, "only {} messages found", messages.len());
+
+        let placeholders = |text: &str| text.matches("{}").count();
+        let mut problems = Vec::new();
+        for (msgid, plural) in messages {
+            let Some(entry) = catalog.get(&msgid) else {
+                problems.push(format!("{:?}: missing", msgid));
+                continue;
+            };
+            if entry.fuzzy {
+                problems.push(format!("{:?}: fuzzy", msgid));
+            }
+            if entry.msgid_plural != plural {
+                problems.push(format!("{:?}: plural {:?} != {:?}", msgid, entry.msgid_plural, plural));
+            }
+            let expected_forms = if plural.is_some() { 2 } else { 1 };
+            if entry.msgstr.len() != expected_forms || entry.msgstr.iter().any(String::is_empty) {
+                problems.push(format!("{:?}: untranslated", msgid));
+            }
+            // fill rellena los marcadores en orden: la traducción debe tener los mismos
+            if entry.msgstr.iter().any(|msgstr| placeholders(msgstr) != placeholders(&msgid)) {
+                problems.push(format!("{:?}: placeholder count differs", msgid));
+            }
+        }
+        assert!(problems.is_empty(), "po/es.po:\n{}", problems.join("\n"));
+    }
+
+    #[test]
+    fn extracts_messages_like_xgettext() {
+        let source = r#"
+            let a = tr("Say \"hi\"");
+            let b = trn(
+                " ({} file, {})",
+                " ({} files, {})", n);
+            let c = text.trim();
+            let d = str_to_cstring("tr(\"no\")");
+            let e = tr(&label);
+        "#;
+        assert_eq!(
+            extract_messages(source),
+            [
+                ("Say \"hi\"".to_string(), None),
+                (" ({} file, {})".to_string(), Some(" ({} files, {})".to_string())),
+            ]
+        );
+    }
+}
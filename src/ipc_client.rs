@@ -1,32 +1,247 @@
 //! Cliente IPC para comunicación con el daemon de G-DriveXP
+//!
+//! Con daemons que anuncian `CAP_MULTIPLEX` todas las peticiones comparten una
+//! conexión: cada una viaja como `Tagged` con su id y una tarea lectora entrega
+//! las respuestas según llegan, así una consulta lenta no retiene a las demás
+//! y abandonar una (timeout) no desalinea el stream. Con daemons anteriores las
+//! peticiones se serializan sobre la conexión como siempre.
 
-use serde::{Deserialize, Serialize};
+use gdrivexp_ipc_proto::{
+    self as proto, ConflictResolution, IpcRequest, IpcResponse, CAP_BATCH_STATUS, CAP_CONFLICTS,
+    CAP_DIRECTORY_STATUS, CAP_EXTENDED_STATUS, CAP_FILE_DETAILS, CAP_FOLDER_SUMMARY,
+    CAP_MOUNT_POINTS, CAP_MULTIPLEX, CAP_RECURSIVE_AVAILABILITY, CAP_SUBSCRIBE,
+    CAP_TRANSFER_PROGRESS, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::io;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::UnixStream;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
 
-use std::cell::RefCell;
+/// Capacidades que anuncia la extensión
+const CLIENT_CAPABILITIES: &[&str] = &[
+    CAP_EXTENDED_STATUS,
+    CAP_SUBSCRIBE,
+    CAP_BATCH_STATUS,
+    CAP_DIRECTORY_STATUS,
+    CAP_FILE_DETAILS,
+    CAP_MOUNT_POINTS,
+    CAP_TRANSFER_PROGRESS,
+    CAP_CONFLICTS,
+    CAP_RECURSIVE_AVAILABILITY,
+    CAP_FOLDER_SUMMARY,
+    CAP_MULTIPLEX,
+];
+
+/// Resultado del handshake con el daemon
+#[derive(Debug, Clone)]
+pub struct ProtocolInfo {
+    pub protocol_version: u32,
+    pub capabilities: Vec<String>,
+}
+
+impl ProtocolInfo {
+    /// Daemon anterior al handshake
+    fn legacy() -> Self {
+        Self {
+            protocol_version: 1,
+            capabilities: Vec::new(),
+        }
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    /// Sabemos decodificar sus respuestas: desde `MIN_PROTOCOL_VERSION` (daemons
+    /// anteriores al handshake, que sólo soportan GetFileStatus/Ping/SetOnlineOnly/
+    /// SetLocalOnline/GetFileAvailability) hasta nuestro `PROTOCOL_VERSION`
+    pub fn is_compatible(&self) -> bool {
+        (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&self.protocol_version)
+    }
+}
 
 /// Cliente IPC que se comunica con el daemon vía Unix Socket
 pub struct IpcClient {
     socket_path: std::path::PathBuf,
-    stream: RefCell<Option<UnixStream>>,
+    /// Conexión actual; se clona el `Rc` para no mantener el borrow durante el I/O
+    connection: RefCell<Option<Rc<Connection>>>,
+    /// Evita que peticiones concurrentes abran varias conexiones a la vez
+    connecting: AsyncMutex<()>,
+    /// Protocolo negociado con el daemon en la conexión actual
+    protocol: RefCell<Option<ProtocolInfo>>,
+}
+
+impl Default for IpcClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IpcClient {
     /// Crea un nuevo cliente IPC
     pub fn new() -> Self {
-        let uid = unsafe { libc::getuid() };
-        let socket_path = std::path::PathBuf::from(format!("/run/user/{}/gdrivexp.sock", uid));
-        
-        crate::log_debug(&format!("IpcClient initialized. UID: {}, Socket Path: {:?}", uid, socket_path));
-        
-        Self { 
+        Self::with_socket_path(crate::config::get().socket_path())
+    }
+
+    /// Cliente con el socket que usaría la extensión (`GDRIVEXP_NAUTILUS_SOCKET`,
+    /// `nautilus.toml` o la ruta estándar), para herramientas fuera de Nautilus.
+    ///
+    /// Devuelve también los errores de la configuración, que nadie más registra.
+    pub fn from_user_config() -> (Self, Vec<String>) {
+        let (config, errors) = crate::config::read();
+        (Self::with_socket_path(config.socket_path()), errors)
+    }
+
+    /// Cliente contra un socket concreto (debug_ipc --socket, pruebas)
+    pub fn with_socket_path(socket_path: std::path::PathBuf) -> Self {
+        log_debug!("IpcClient initialized. Socket Path: {:?}", socket_path);
+
+        Self {
             socket_path,
-            stream: RefCell::new(None),
+            connection: RefCell::new(None),
+            connecting: AsyncMutex::new(()),
+            protocol: RefCell::new(None),
+        }
+    }
+
+    /// Protocolo negociado en la conexión actual (None si aún no hubo conexión)
+    pub fn protocol_info(&self) -> Option<ProtocolInfo> {
+        self.protocol.borrow().clone()
+    }
+
+    /// La conexión actual admite varias peticiones en vuelo
+    pub fn is_multiplexed(&self) -> bool {
+        self.live_connection()
+            .is_some_and(|connection| matches!(*connection, Connection::Multiplexed(_)))
+    }
+
+    /// Versión negociada en la conexión actual (1 = daemon anterior al handshake)
+    fn protocol_version(&self) -> u32 {
+        self.protocol.borrow().as_ref().map_or(1, |p| p.protocol_version)
+    }
+
+    /// Indica si el daemon conectado anunció la capacidad dada
+    fn supports(&self, capability: &str) -> bool {
+        self.protocol.borrow().as_ref().is_some_and(|p| p.supports(capability))
+    }
+
+    /// Abre una conexión y negocia la versión de protocolo.
+    ///
+    /// Los daemons anteriores al handshake responden `Error` o cierran la conexión
+    /// ante `Hello`; en ese caso se reconecta y se asume el protocolo v1.
+    async fn connect(&self) -> io::Result<UnixStream> {
+        let mut stream = UnixStream::connect(&self.socket_path).await?;
+
+        let hello = IpcRequest::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CLIENT_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        };
+
+        let handshake = match proto::write_message(&mut stream, &hello).await {
+            // La versión aún no se conoce: decodificar como el daemon más antiguo
+            Ok(()) => proto::read_frame(&mut stream)
+                .await
+                .and_then(|payload| proto::decode_response(&payload, 1)),
+            Err(e) => Err(e),
+        };
+
+        let info = match handshake {
+            Ok(IpcResponse::Hello { protocol_version, capabilities }) => ProtocolInfo {
+                protocol_version,
+                capabilities,
+            },
+            Ok(other) => {
+                log_debug!("Daemon does not support Hello ({:?}), assuming protocol v1", other);
+                ProtocolInfo::legacy()
+            }
+            Err(e) => {
+                log_warn!("Hello failed ({}), reconnecting with protocol v1", e);
+                stream = UnixStream::connect(&self.socket_path).await?;
+                ProtocolInfo::legacy()
+            }
+        };
+
+        if info.is_compatible() {
+            log_debug!(
+                "Daemon protocol v{} (extension v{}), capabilities: {:?}",
+                info.protocol_version, PROTOCOL_VERSION, info.capabilities
+            );
+        } else if info.protocol_version > PROTOCOL_VERSION {
+            log_error!(
+                "INCOMPATIBLE daemon protocol v{}: this extension only understands up to v{}. \
+                 Update the Nautilus extension; status emblems are disabled until then.",
+                info.protocol_version, PROTOCOL_VERSION
+            );
+        } else {
+            log_error!(
+                "INCOMPATIBLE daemon protocol v{}: this extension requires v{} or newer. \
+                 Update G-DriveXP; status emblems are disabled until then.",
+                info.protocol_version, MIN_PROTOCOL_VERSION
+            );
+        }
+
+        // Cada (re)conexión refresca los puntos de montaje: pueden cambiar si el daemon se reinició
+        if !info.is_compatible() {
+            // No sabríamos leer la respuesta
+        } else if info.supports(CAP_MOUNT_POINTS) {
+            match Self::fetch_mount_points(&mut stream, info.protocol_version).await {
+                Ok(roots) => crate::mount_points::update(roots),
+                Err(e) => log_warn!("GetMountPoints failed: {}", e),
+            }
+        } else {
+            crate::mount_points::mark_unsupported();
+        }
+
+        *self.protocol.borrow_mut() = Some(info);
+        Ok(stream)
+    }
+
+    async fn fetch_mount_points(stream: &mut UnixStream, protocol_version: u32) -> io::Result<Vec<String>> {
+        let request_bytes = proto::encode(&IpcRequest::GetMountPoints)?;
+        match Self::perform_io(stream, &request_bytes, protocol_version).await? {
+            IpcResponse::MountPoints(roots) => Ok(roots),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetMountPoints: {:?}", other),
+            )),
         }
     }
     
+    /// Comprueba que el daemon responde
+    pub async fn ping(&self) -> io::Result<()> {
+        match self.send_request(IpcRequest::Ping).await? {
+            IpcResponse::Pong => Ok(()),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a Ping: {:?}", other),
+            )),
+        }
+    }
+
+    /// Raíces de montaje que gestiona el daemon
+    pub async fn get_mount_points(&self) -> io::Result<Vec<String>> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_MOUNT_POINTS) {
+            return Err(unsupported(CAP_MOUNT_POINTS));
+        }
+
+        match self.send_request(IpcRequest::GetMountPoints).await? {
+            IpcResponse::MountPoints(roots) => Ok(roots),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetMountPoints: {:?}", other),
+            )),
+        }
+    }
+
     /// Consulta el estado de sincronización y compartido de un archivo
     pub async fn get_extended_status(&self, path: &str) -> io::Result<crate::FileStatusData> {
         let request = IpcRequest::GetFileStatus {
@@ -35,16 +250,108 @@ impl IpcClient {
         
         match self.send_request(request).await? {
             IpcResponse::ExtendedStatus(data) => Ok(data),
-            _ => Ok(crate::FileStatusData {
-                status: crate::SyncStatus::Unknown,
-                availability: crate::FileAvailability::NotTracked,
-                is_shared: false,
-            }),
+            // Daemon sin CAP_EXTENDED_STATUS: sólo devuelve el SyncStatus
+            IpcResponse::FileStatus(status) => {
+                let availability = match self.get_availability(path).await {
+                    Ok(availability) => availability,
+                    Err(_) => crate::FileAvailability::NotTracked,
+                };
+                Ok(crate::FileStatusData {
+                    status,
+                    availability,
+                    ..crate::FileStatusData::unknown()
+                })
+            }
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(crate::FileStatusData::unknown()),
+        }
+    }
+
+    /// Consulta la disponibilidad (local/online) de un archivo
+    pub async fn get_availability(&self, path: &str) -> io::Result<crate::FileAvailability> {
+        let request = IpcRequest::GetFileAvailability {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::Availability(availability) => Ok(availability),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(crate::FileAvailability::NotTracked),
         }
     }
 
 
     
+    /// Consulta el estado de varios archivos en un solo round-trip.
+    ///
+    /// El resultado respeta el orden de `paths`. Devuelve error si el daemon no
+    /// soporta la consulta por lotes, para que el llamador recurra a consultas individuales.
+    pub async fn get_extended_status_batch(&self, paths: &[String]) -> io::Result<Vec<crate::FileStatusData>> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_BATCH_STATUS) {
+            return Err(unsupported(CAP_BATCH_STATUS));
+        }
+
+        let request = IpcRequest::GetFileStatusBatch {
+            paths: paths.to_vec(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::ExtendedStatusBatch(data) if data.len() == paths.len() => Ok(data),
+            IpcResponse::ExtendedStatusBatch(data) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Lote incompleto: {} respuestas para {} paths", data.len(), paths.len()),
+            )),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetFileStatusBatch: {:?}", other),
+            )),
+        }
+    }
+
+    /// Consulta el estado de todas las entradas directas de un directorio
+    pub async fn get_directory_status(&self, path: &str) -> io::Result<Vec<(String, crate::FileStatusData)>> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_DIRECTORY_STATUS) {
+            return Err(unsupported(CAP_DIRECTORY_STATUS));
+        }
+
+        let request = IpcRequest::GetDirectoryStatus {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::DirectoryStatus(entries) => Ok(entries),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetDirectoryStatus: {:?}", other),
+            )),
+        }
+    }
+
+    /// Consulta el detalle completo de un archivo (diálogo de Propiedades)
+    pub async fn get_file_details(&self, path: &str) -> io::Result<proto::FileDetails> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_FILE_DETAILS) {
+            return Err(unsupported(CAP_FILE_DETAILS));
+        }
+
+        let request = IpcRequest::GetFileDetails {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::FileDetails(details) => Ok(details),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetFileDetails: {:?}", other),
+            )),
+        }
+    }
+
     /// Cambia archivo a online_only
     pub async fn set_online_only(&self, path: &str) -> io::Result<bool> {
         let request = IpcRequest::SetOnlineOnly {
@@ -53,6 +360,7 @@ impl IpcClient {
         
         match self.send_request(request).await? {
             IpcResponse::Success => Ok(true),  // CAMBIADO de Ok a Success
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
             _ => Ok(false),
         }
     }
@@ -65,57 +373,215 @@ impl IpcClient {
         
         match self.send_request(request).await? {
             IpcResponse::Success => Ok(true),  // CAMBIADO de Ok a Success
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
             _ => Ok(false),
         }
     }
 
     
+    /// Fuerza una sincronización inmediata de una carpeta
+    pub async fn sync_now(&self, path: &str) -> io::Result<bool> {
+        let request = IpcRequest::SyncNow {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::Success => Ok(true),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(false),
+        }
+    }
+
+    /// Pausa la sincronización de una carpeta
+    pub async fn pause_sync(&self, path: &str) -> io::Result<bool> {
+        let request = IpcRequest::PauseSync {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::Success => Ok(true),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(false),
+        }
+    }
+
+    /// Resuelve el conflicto de un archivo con la estrategia indicada
+    pub async fn resolve_conflict(&self, path: &str, resolution: ConflictResolution) -> io::Result<bool> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_CONFLICTS) {
+            return Err(unsupported(CAP_CONFLICTS));
+        }
+
+        let request = IpcRequest::ResolveConflict {
+            path: path.to_string(),
+            resolution,
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::Success => Ok(true),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(false),
+        }
+    }
+
+    /// Reintenta la sincronización de archivos en estado de error
+    pub async fn retry_sync(&self, paths: &[String]) -> io::Result<bool> {
+        let request = IpcRequest::RetrySync {
+            paths: paths.to_vec(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::Success => Ok(true),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            _ => Ok(false),
+        }
+    }
+
+    /// Cambia la disponibilidad de una carpeta y todo su contenido.
+    ///
+    /// Con `dry_run` sólo devuelve el alcance sin aplicar nada. Si el daemon no
+    /// soporta la operación recursiva se aplica sólo a `path` y el alcance es `None`.
+    pub async fn set_availability_recursive(
+        &self,
+        path: &str,
+        availability: crate::FileAvailability,
+        dry_run: bool,
+    ) -> io::Result<Option<proto::AvailabilityImpact>> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_RECURSIVE_AVAILABILITY) {
+            if dry_run {
+                return Ok(None);
+            }
+            let applied = match availability {
+                crate::FileAvailability::LocalOnline => self.set_local_online(path).await?,
+                _ => self.set_online_only(path).await?,
+            };
+            return if applied {
+                Ok(None)
+            } else {
+                Err(io::Error::other("El daemon rechazó el cambio de disponibilidad"))
+            };
+        }
+
+        let request = IpcRequest::SetAvailability {
+            path: path.to_string(),
+            availability,
+            recursive: true,
+            dry_run,
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::AvailabilityChange(impact) => Ok(Some(impact)),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a SetAvailability: {:?}", other),
+            )),
+        }
+    }
+
+    /// Recuento recursivo de estados del contenido de una carpeta
+    pub async fn get_folder_summary(&self, path: &str) -> io::Result<proto::FolderSummary> {
+        self.ensure_connected().await;
+        if !self.supports(CAP_FOLDER_SUMMARY) {
+            return Err(unsupported(CAP_FOLDER_SUMMARY));
+        }
+
+        let request = IpcRequest::GetFolderSummary {
+            path: path.to_string(),
+        };
+
+        match self.send_request(request).await? {
+            IpcResponse::FolderSummary(summary) => Ok(summary),
+            IpcResponse::Error { message } => Err(io::Error::other(message)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Respuesta inesperada a GetFolderSummary: {:?}", other),
+            )),
+        }
+    }
+
+    /// Conecta (y negocia el protocolo) si aún no hay conexión abierta
+    async fn ensure_connected(&self) {
+        if let Err(e) = self.current_connection().await {
+            log_debug!("Connection failed: {}", e);
+        }
+    }
+
+    /// Conexión abierta y utilizable, si la hay
+    fn live_connection(&self) -> Option<Rc<Connection>> {
+        self.connection
+            .borrow()
+            .as_ref()
+            .filter(|connection| !connection.is_broken())
+            .cloned()
+    }
+
+    /// Conexión actual, abriendo una nueva si no hay ninguna utilizable
+    async fn current_connection(&self) -> io::Result<Rc<Connection>> {
+        if let Some(connection) = self.live_connection() {
+            return Ok(connection);
+        }
+
+        let _connecting = self.connecting.lock().await;
+        // Otra petición pudo conectar mientras esperábamos
+        if let Some(connection) = self.live_connection() {
+            return Ok(connection);
+        }
+
+        let stream = self.connect().await?;
+        let info = self.protocol_info().unwrap_or_else(ProtocolInfo::legacy);
+        let connection = Rc::new(Connection::new(stream, &info));
+        log_debug!(
+            "Connected successfully ({})",
+            if matches!(*connection, Connection::Multiplexed(_)) { "multiplexed" } else { "serial" }
+        );
+        *self.connection.borrow_mut() = Some(connection.clone());
+        Ok(connection)
+    }
+
+    /// Olvida la conexión si sigue siendo la actual
+    fn discard(&self, connection: &Rc<Connection>) {
+        let mut current = self.connection.borrow_mut();
+        if current.as_ref().is_some_and(|c| Rc::ptr_eq(c, connection)) {
+            *current = None;
+        }
+    }
+
     /// Helper genérico para enviar requests con reconexión automática
     async fn send_request(&self, request: IpcRequest) -> io::Result<IpcResponse> {
-        // Serializar request
-        let request_bytes = bincode::serialize(&request)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        
-        // Intentar usar la conexión existente o reconectar
         let mut attempts = 0;
         loop {
             attempts += 1;
-            
+
             // Garantizar que tenemos una conexión
-            if self.stream.borrow().is_none() {
-                crate::log_debug(&format!("Connecting attempt {}", attempts));
-                match UnixStream::connect(&self.socket_path).await {
-                    Ok(s) => {
-                        crate::log_debug("Connected successfully");
-                        *self.stream.borrow_mut() = Some(s);
-                    },
-                    Err(e) => {
-                        crate::log_debug(&format!("Connection failed: {}", e));
-                        return Ok(IpcResponse::Error {
-                            message: "Daemon no disponible".to_string(),
-                        });
-                    }
+            let connection = match self.current_connection().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    // Daemon parado: se repetiría en cada archivo mostrado
+                    log_debug!("Connection failed (attempt {}): {}", attempts, e);
+                    return Ok(IpcResponse::Error {
+                        message: "Daemon no disponible".to_string(),
+                    });
                 }
+            };
+
+            if self.protocol.borrow().as_ref().is_some_and(|p| !p.is_compatible()) {
+                return Ok(IpcResponse::Error {
+                    message: "Protocolo del daemon incompatible".to_string(),
+                });
             }
 
-            // Realizar I/O con borrow mutable del stream
-            // Necesitamos extraer temporalmente el stream o usar un alcance limitado
-            let mut stream_opt = self.stream.borrow_mut();
-            if let Some(stream) = stream_opt.as_mut() {
-                 match Self::perform_io(stream, &request_bytes).await {
-                     Ok(response) => return Ok(response),
-                     Err(_e) => {
-                         crate::log_debug(&format!("IO Error: {}", _e));
-                         // Si falló el I/O, el stream probablemente está roto
-                         // Lo eliminamos y reintentamos si no hemos excedido intentos
-                         // eprintln!("IPC Error (intento {}): {}", attempts, e);
-                     }
-                 }
+            match connection.call(&request).await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    // Si falló el I/O, el stream probablemente está roto:
+                    // lo descartamos y reintentamos si no hemos excedido intentos
+                    log_warn!("IO Error: {}", e);
+                    self.discard(&connection);
+                }
             }
-            
-            // Si llegamos aquí, invalidamos el stream
-            *stream_opt = None;
-            
+
             if attempts >= 2 {
                 return Ok(IpcResponse::Error {
                     message: "Error de comunicación IPC tras reintentos".to_string(),
@@ -124,52 +590,256 @@ impl IpcClient {
         }
     }
 
-    async fn perform_io(stream: &mut UnixStream, request_bytes: &[u8]) -> io::Result<IpcResponse> {
-        // Enviar longitud + request
-        let len = (request_bytes.len() as u32).to_be_bytes();
-        stream.write_all(&len).await?;
-        stream.write_all(request_bytes).await?;
-        
-        // Leer longitud de respuesta
-        let mut len_buf = [0u8; 4];
-        stream.read_exact(&mut len_buf).await?;
-        let response_len = u32::from_be_bytes(len_buf) as usize;
-        
-        if response_len > 4096 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Respuesta IPC demasiado grande",
-            ));
+    /// Abre una conexión dedicada y se suscribe a los cambios de estado del daemon.
+    ///
+    /// La conexión no se comparte con `send_request`: tras el `Subscribe` el daemon
+    /// sólo envía frames `StatusChanged` por ella.
+    pub async fn subscribe(&self) -> io::Result<Subscription> {
+        let mut stream = self.connect().await?;
+        if self.protocol.borrow().as_ref().is_some_and(|p| !p.is_compatible()) {
+            return Err(io::Error::other("Protocolo del daemon incompatible"));
+        }
+        if !self.supports(CAP_SUBSCRIBE) {
+            return Err(unsupported(CAP_SUBSCRIBE));
+        }
+
+        proto::write_message(&mut stream, &IpcRequest::Subscribe).await?;
+
+        log_debug!("Subscribed to daemon status changes");
+        Ok(Subscription {
+            stream,
+            protocol_version: self.protocol_version(),
+        })
+    }
+
+    async fn perform_io(stream: &mut UnixStream, request_bytes: &[u8], protocol_version: u32) -> io::Result<IpcResponse> {
+        proto::write_frame(stream, request_bytes).await?;
+        let payload = proto::read_frame(stream).await?;
+        proto::decode_response(&payload, protocol_version)
+    }
+}
+
+/// Suscripción activa a los cambios de estado emitidos por el daemon
+pub struct Subscription {
+    stream: UnixStream,
+    protocol_version: u32,
+}
+
+impl Subscription {
+    /// Espera el siguiente lote de paths cuyo estado cambió.
+    ///
+    /// Devuelve error si el daemon cerró la conexión; el llamador debe volver a suscribirse.
+    pub async fn next_changes(&mut self) -> io::Result<Vec<String>> {
+        loop {
+            let payload = proto::read_frame(&mut self.stream).await?;
+            match proto::decode_response(&payload, self.protocol_version)? {
+                IpcResponse::StatusChanged { paths } => return Ok(paths),
+                IpcResponse::Error { message } => {
+                    return Err(io::Error::other(message));
+                }
+                // Confirmación del Subscribe u otros frames que no nos interesan
+                _ => continue,
+            }
         }
-        
-        // Leer respuesta
-        let mut response_buf = vec![0u8; response_len];
-        stream.read_exact(&mut response_buf).await?;
-        
-        // Deserializar respuesta
-        bincode::deserialize(&response_buf)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
 }
 
-/// Request IPC (debe coincidir EXACTAMENTE con src/ipc/mod.rs del daemon)
-#[derive(Debug, Serialize, Deserialize)]
-enum IpcRequest {
-    GetFileStatus { path: String },
-    Ping,
-    SetOnlineOnly { path: String },
-    SetLocalOnline { path: String },
-    GetFileAvailability { path: String },
+fn unsupported(capability: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("El daemon no soporta '{}'", capability),
+    )
 }
 
-/// Respuesta IPC (debe coincidir EXACTAMENTE con src/ipc/mod.rs del daemon)
-#[derive(Debug, Serialize, Deserialize)]
-enum IpcResponse {
-    FileStatus(crate::SyncStatus),
-    ExtendedStatus(crate::FileStatusData),
-    Pong,
-    Availability(crate::FileAvailability),
-    Success,  // ¡CAMBIADO de Ok a Success!
-    Error { message: String },
+/// Conexión con el daemon compartida por las peticiones de un `IpcClient`
+enum Connection {
+    /// Daemon sin CAP_MULTIPLEX: una petición cada vez, en orden
+    Serial {
+        stream: AsyncMutex<UnixStream>,
+        protocol_version: u32,
+        /// Petición fallida o abandonada a medias: su respuesta desalinearía el stream
+        interrupted: Cell<bool>,
+    },
+    Multiplexed(Multiplexer),
 }
 
+impl Connection {
+    fn new(stream: UnixStream, info: &ProtocolInfo) -> Self {
+        // Las respuestas Tagged sólo se decodifican con el formato v4
+        if info.protocol_version >= 4 && info.supports(CAP_MULTIPLEX) {
+            Connection::Multiplexed(Multiplexer::new(stream, info.protocol_version))
+        } else {
+            Connection::Serial {
+                stream: AsyncMutex::new(stream),
+                protocol_version: info.protocol_version,
+                interrupted: Cell::new(false),
+            }
+        }
+    }
+
+    fn is_broken(&self) -> bool {
+        match self {
+            Connection::Serial { interrupted, .. } => interrupted.get(),
+            Connection::Multiplexed(mux) => mux.is_broken(),
+        }
+    }
+
+    async fn call(&self, request: &IpcRequest) -> io::Result<IpcResponse> {
+        match self {
+            Connection::Serial {
+                stream,
+                protocol_version,
+                interrupted,
+            } => {
+                let request_bytes = proto::encode(request)?;
+                let mut stream = stream.lock().await;
+                if interrupted.get() {
+                    return Err(connection_closed());
+                }
+                let guard = InterruptGuard(interrupted);
+                let result = IpcClient::perform_io(&mut stream, &request_bytes, *protocol_version).await;
+                if result.is_ok() {
+                    std::mem::forget(guard);
+                }
+                result
+            }
+            Connection::Multiplexed(mux) => mux.call(request).await,
+        }
+    }
+}
+
+/// Respuestas pendientes por id de petición
+type PendingResponses = Arc<Mutex<HashMap<u64, oneshot::Sender<IpcResponse>>>>;
+
+/// Conexión con varias peticiones `Tagged` en vuelo
+struct Multiplexer {
+    writer: AsyncMutex<OwnedWriteHalf>,
+    pending: PendingResponses,
+    next_id: Cell<u64>,
+    /// Escritura abandonada a medias (el frame quedó incompleto)
+    write_interrupted: Cell<bool>,
+    /// El lector terminó (daemon cerrado o frame inválido)
+    closed: Arc<AtomicBool>,
+    reader: tokio::task::JoinHandle<()>,
+}
+
+impl Multiplexer {
+    fn new(stream: UnixStream, protocol_version: u32) -> Self {
+        let (read_half, write_half) = stream.into_split();
+        let pending = PendingResponses::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let reader = tokio::spawn(read_responses(read_half, protocol_version, pending.clone(), closed.clone()));
+
+        Self {
+            writer: AsyncMutex::new(write_half),
+            pending,
+            next_id: Cell::new(1),
+            write_interrupted: Cell::new(false),
+            closed,
+            reader,
+        }
+    }
+
+    fn is_broken(&self) -> bool {
+        self.write_interrupted.get() || self.closed.load(Ordering::Acquire)
+    }
+
+    async fn call(&self, request: &IpcRequest) -> io::Result<IpcResponse> {
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1));
+        let payload = proto::encode(&IpcRequest::Tagged {
+            id,
+            request: Box::new(request.clone()),
+        })?;
+
+        let (response_tx, response_rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, response_tx);
+        // Quita la entrada si la petición se abandona: la respuesta tardía se descarta
+        let _pending = PendingGuard {
+            pending: &self.pending,
+            id,
+        };
+        // El lector vacía `pending` después de marcar `closed`
+        if self.closed.load(Ordering::Acquire) {
+            return Err(connection_closed());
+        }
+
+        {
+            let mut writer = self.writer.lock().await;
+            if self.write_interrupted.get() {
+                return Err(connection_closed());
+            }
+            let guard = InterruptGuard(&self.write_interrupted);
+            proto::write_frame(&mut *writer, &payload).await?;
+            std::mem::forget(guard);
+        }
+
+        response_rx.await.map_err(|_| connection_closed())
+    }
+}
+
+impl Drop for Multiplexer {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Marca la conexión como inservible si el I/O falla o se abandona a medias
+/// (timeout): el resto del frame desalinearía el stream
+struct InterruptGuard<'a>(&'a Cell<bool>);
+
+impl Drop for InterruptGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+struct PendingGuard<'a> {
+    pending: &'a PendingResponses,
+    id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Entrega cada respuesta `Tagged` a la petición que la espera
+async fn read_responses(
+    mut reader: OwnedReadHalf,
+    protocol_version: u32,
+    pending: PendingResponses,
+    closed: Arc<AtomicBool>,
+) {
+    loop {
+        let response = match proto::read_frame(&mut reader)
+            .await
+            .and_then(|payload| proto::decode_response(&payload, protocol_version))
+        {
+            Ok(response) => response,
+            Err(e) => {
+                log_debug!("Multiplexed connection closed: {}", e);
+                break;
+            }
+        };
+
+        match response {
+            IpcResponse::Tagged { id, response } => match pending.lock().unwrap().remove(&id) {
+                Some(response_tx) => {
+                    let _ = response_tx.send(*response);
+                }
+                None => log_debug!("Discarding response {} (request abandoned)", id),
+            },
+            other => log_warn!("Untagged frame on multiplexed connection: {:?}", other),
+        }
+    }
+
+    closed.store(true, Ordering::Release);
+    // Las peticiones en vuelo reciben error al soltar sus Sender
+    pending.lock().unwrap().clear();
+}
+
+fn connection_closed() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "Conexión IPC cerrada")
+}
@@ -2,74 +2,56 @@
 //!
 //! Muestra emblemas de sincronización en archivos montados por G-DriveXP.
 
+#[macro_use]
+mod logging;
+mod config;
+mod config_watch;
 mod ffi;
-mod ipc_client;
+mod folder_summary;
+mod i18n;
+mod logic;
+pub mod ipc_client;
+mod mount_points;
 mod provider;
+mod status_cache;
+mod subscription;
+mod transfers;
+mod work_queue;
 pub mod menu_provider;
+mod column_provider;
+mod properties_provider;
 
 use glib_sys::GType;
 use gobject_sys::GTypeModule;
 use std::os::raw::c_int;
 
-/// Estado de sincronización (debe coincidir con src/ipc/mod.rs del daemon)
-/// - Synced: Local + Drive (verde)
-/// - CloudOnly: Solo en Drive, no descargado (azul)
-/// - LocalOnly: Solo local, pendiente de subir (naranja)
-/// - Error: Error de sincronización (rojo)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
-pub enum SyncStatus {
-    Synced,      // Verde: en local y en drive
-    CloudOnly,   // Azul: solo en drive
-    LocalOnly,   // Naranja: solo local (pending upload)
-    Error,       // Rojo: error de sincronización
-    Unknown,     // Sin emblema
-}
-
-/// Disponibilidad de un archivo (debe coincidir con src/ipc/mod.rs del daemon)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
-pub enum FileAvailability {
-    LocalOnline,
-    OnlineOnly,
-    NotTracked,
-}
-
-/// Datos completos de estado del archivo (debe coincidir con src/ipc/mod.rs del daemon)
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct FileStatusData {
-    pub status: SyncStatus,
-    pub availability: FileAvailability,
-    pub is_shared: bool,
-}
+// Tipos del protocolo IPC compartidos con el daemon y debug_ipc
+pub use gdrivexp_ipc_proto::{FileAvailability, FileStatusData, SyncStatus};
 
 // ============================================================
 // Funciones exportadas requeridas por Nautilus
 // ============================================================
 
-// Helper de logging
-pub fn log_debug(msg: &str) {
-    use std::io::Write;
-    if let Ok(mut file) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("/tmp/gdrivexp-nautilus-init.log")
-    {
-        let _ = writeln!(file, "{}", msg);
-    }
-}
-
 /// Llamada cuando la extensión es cargada
 #[no_mangle]
 pub unsafe extern "C" fn nautilus_module_initialize(module: *mut GTypeModule) {
-    log_debug("nautilus_module_initialize called");
+    // Antes que nada: decide dónde va el log
+    config::load();
+    log_debug!("nautilus_module_initialize called");
+    i18n::init();
     // Registrar nuestro tipo GDriveXPProvider
     provider::register_type(module);
-    log_debug("provider registered");
+    log_info!("provider registered");
+    // Escuchar cambios del daemon para refrescar emblemas sin re-abrir la carpeta
+    subscription::start();
+    // Aplicar cambios de nautilus.toml sin reiniciar Nautilus
+    config_watch::start();
 }
 
 /// Llamada cuando la extensión es descargada
 #[no_mangle]
 pub unsafe extern "C" fn nautilus_module_shutdown() {
-    log_debug("nautilus_module_shutdown called");
+    log_debug!("nautilus_module_shutdown called");
     // Cleanup si es necesario
 }
 
@@ -79,12 +61,12 @@ pub unsafe extern "C" fn nautilus_module_list_types(
     types: *mut *const GType,
     num_types: *mut c_int,
 ) {
-    log_debug("nautilus_module_list_types called");
+    log_debug!("nautilus_module_list_types called");
     static mut TYPE_LIST: [GType; 1] = [0];
     
     TYPE_LIST[0] = provider::get_type();
     
     *types = std::ptr::addr_of!(TYPE_LIST) as *const GType;
     *num_types = 1;
-    log_debug("types listed");
+    log_debug!("types listed");
 }
@@ -0,0 +1,384 @@
+//! Log de la extensión
+//!
+//! Un único punto de salida con niveles. El filtro sale de `GDRIVEXP_NAUTILUS_LOG`
+//! o de `logging.level` en `nautilus.toml` (p. ej. `warn` o `info,provider=debug`)
+//! y el destino es journald o `$XDG_STATE_HOME/gdrivexp/nautilus.log`, rotado
+//! por tamaño.
+//!
+//! Las macros `log_error!` … `log_trace!` comprueban el nivel antes de formatear
+//! el mensaje: con el log desactivado sólo cuesta una lectura atómica.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Socket del protocolo nativo de journald
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Identificador de la extensión en el journal
+const SYSLOG_IDENTIFIER: &str = "gdrivexp-nautilus";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// Prioridad syslog equivalente
+    fn priority(self) -> u8 {
+        match self {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        }
+    }
+}
+
+/// `off` → `Some(None)`; nivel desconocido → `None`
+fn parse_level(text: &str) -> Option<Option<Level>> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "off" | "none" => Some(None),
+        "error" => Some(Some(Level::Error)),
+        "warn" | "warning" => Some(Some(Level::Warn)),
+        "info" => Some(Some(Level::Info)),
+        "debug" => Some(Some(Level::Debug)),
+        "trace" => Some(Some(Level::Trace)),
+        _ => None,
+    }
+}
+
+/// Filtro de niveles: uno por defecto y, opcionalmente, uno por módulo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: Option<Level>,
+    /// (nombre del módulo sin el crate, p. ej. `provider`; nivel)
+    modules: Vec<(String, Option<Level>)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: Some(Level::Warn),
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    /// Interpreta `nivel[,modulo=nivel...]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self {
+            default: Some(Level::Warn),
+            modules: Vec::new(),
+        };
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let level = parse_level(level)
+                        .ok_or_else(|| format!("nivel de log desconocido '{}'", level.trim()))?;
+                    filter.modules.push((module.trim().to_string(), level));
+                }
+                None => {
+                    filter.default = parse_level(directive)
+                        .ok_or_else(|| format!("nivel de log desconocido '{}'", directive))?;
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    fn level_for(&self, module_path: &str) -> Option<Level> {
+        let module = module_path.split_once("::").map_or("", |(_, rest)| rest);
+        self.modules
+            .iter()
+            .find(|(name, _)| module == name || module.starts_with(&format!("{}::", name)))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> u8 {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .chain([self.default])
+            .map(|level| level.map_or(0, |l| l as u8))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Destino del log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogOutput {
+    /// journald si está disponible, si no fichero
+    Auto,
+    Journal,
+    File,
+}
+
+impl LogOutput {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(LogOutput::Auto),
+            "journal" | "journald" => Ok(LogOutput::Journal),
+            "file" => Ok(LogOutput::File),
+            other => Err(format!("destino de log desconocido '{}' (auto, journal o file)", other)),
+        }
+    }
+}
+
+/// Parte de la configuración que afecta al log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogSettings {
+    pub filter: LogFilter,
+    pub output: LogOutput,
+    /// `None` usa `$XDG_STATE_HOME/gdrivexp/nautilus.log`
+    pub file: Option<PathBuf>,
+    /// Tamaño a partir del cual se rota el fichero
+    pub max_size: u64,
+    /// Ficheros rotados que se conservan (`nautilus.log.1` …)
+    pub keep: u32,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            filter: LogFilter::default(),
+            output: LogOutput::Auto,
+            file: None,
+            max_size: 1024 * 1024,
+            keep: 3,
+        }
+    }
+}
+
+// ============================================================
+// Estado global
+// ============================================================
+
+/// Nivel máximo habilitado en algún módulo (0 = log desactivado)
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+static FILTER: RwLock<Option<LogFilter>> = RwLock::new(None);
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+enum Sink {
+    Journal(UnixDatagram),
+    File(RotatingFile),
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+    max_size: u64,
+    keep: u32,
+}
+
+/// Aplica (o vuelve a aplicar tras una recarga) la configuración del log
+pub fn configure(settings: &LogSettings) {
+    let max_level = settings.filter.max_level();
+    if let Ok(mut filter) = FILTER.write() {
+        *filter = Some(settings.filter.clone());
+    }
+
+    let sink = if max_level == 0 { None } else { open_sink(settings) };
+    if let Ok(mut current) = SINK.lock() {
+        *current = sink;
+    }
+    MAX_LEVEL.store(max_level, Ordering::Release);
+}
+
+/// Comprobación barata previa a formatear el mensaje (usada por las macros)
+#[inline]
+pub fn enabled(level: Level, module_path: &str) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    FILTER
+        .read()
+        .ok()
+        .and_then(|filter| filter.as_ref().map(|f| f.level_for(module_path)))
+        .flatten()
+        .is_some_and(|max| level <= max)
+}
+
+pub fn write(level: Level, module_path: &str, message: &str) {
+    let Ok(mut sink) = SINK.lock() else { return };
+    let module = module_path.split_once("::").map_or(module_path, |(_, rest)| rest);
+    match sink.as_mut() {
+        Some(Sink::Journal(socket)) => {
+            let _ = socket.send_to(&journal_entry(level, module, message), JOURNAL_SOCKET);
+        }
+        Some(Sink::File(file)) => {
+            let line = format!("{} {:5} {}: {}\n", timestamp(), level.name(), module, message);
+            file.write_line(line.as_bytes());
+        }
+        None => {}
+    }
+}
+
+fn open_sink(settings: &LogSettings) -> Option<Sink> {
+    let journal = match settings.output {
+        LogOutput::Journal => true,
+        LogOutput::File => false,
+        LogOutput::Auto => settings.file.is_none() && Path::new(JOURNAL_SOCKET).exists(),
+    };
+    if journal {
+        if let Ok(socket) = UnixDatagram::unbound() {
+            return Some(Sink::Journal(socket));
+        }
+    }
+
+    let path = settings.file.clone().or_else(default_log_path)?;
+    Some(Sink::File(RotatingFile::open(path, settings.max_size, settings.keep)))
+}
+
+/// `$XDG_STATE_HOME/gdrivexp/nautilus.log` (o `~/.local/state/...`)
+pub fn default_log_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(base.join("gdrivexp").join("nautilus.log"))
+}
+
+/// Entrada en el protocolo nativo de journald (MESSAGE en formato binario por si
+/// contiene saltos de línea)
+fn journal_entry(level: Level, module: &str, message: &str) -> Vec<u8> {
+    let mut entry = format!(
+        "PRIORITY={}\nSYSLOG_IDENTIFIER={}\nGDRIVEXP_MODULE={}\nMESSAGE\n",
+        level.priority(),
+        SYSLOG_IDENTIFIER,
+        module
+    )
+    .into_bytes();
+    entry.extend_from_slice(&(message.len() as u64).to_le_bytes());
+    entry.extend_from_slice(message.as_bytes());
+    entry.push(b'\n');
+    entry
+}
+
+fn timestamp() -> String {
+    unsafe {
+        let now = glib_sys::g_date_time_new_now_local();
+        if now.is_null() {
+            return String::new();
+        }
+        let format = crate::ffi::str_to_cstring("%Y-%m-%d %H:%M:%S");
+        let formatted = crate::ffi::gchar_to_string_free(glib_sys::g_date_time_format(now, format.as_ptr()));
+        glib_sys::g_date_time_unref(now);
+        formatted.unwrap_or_default()
+    }
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, keep: u32) -> Self {
+        let mut rotating = Self {
+            path,
+            file: None,
+            size: 0,
+            max_size,
+            keep,
+        };
+        rotating.reopen();
+        rotating
+    }
+
+    /// Directorio privado y O_NOFOLLOW: un enlace simbólico plantado en la ruta
+    /// no redirige la escritura
+    fn reopen(&mut self) {
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir);
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_CLOEXEC)
+            .open(&self.path)
+            .ok();
+        self.size = self
+            .file
+            .as_ref()
+            .and_then(|f| f.metadata().ok())
+            .map_or(0, |m| m.len());
+    }
+
+    fn write_line(&mut self, line: &[u8]) {
+        let Some(file) = self.file.as_mut() else { return };
+        if file.write_all(line).is_ok() {
+            self.size += line.len() as u64;
+        }
+        if self.size >= self.max_size {
+            self.rotate();
+        }
+    }
+
+    /// nautilus.log → nautilus.log.1 → … → nautilus.log.{keep} (el más antiguo se pierde)
+    fn rotate(&mut self) {
+        self.file = None;
+        let rotated = |n: u32| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.keep == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(rotated(n), rotated(n + 1));
+            }
+            let _ = std::fs::rename(&self.path, rotated(1));
+        }
+        self.reopen();
+    }
+}
+
+// ============================================================
+// Macros
+// ============================================================
+
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($level, module_path!()) {
+            $crate::logging::write($level, module_path!(), &format!($($arg)+));
+        }
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Error, $($arg)+) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Warn, $($arg)+) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Info, $($arg)+) };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Debug, $($arg)+) };
+}
+
+macro_rules! log_trace {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Trace, $($arg)+) };
+}
@@ -0,0 +1,511 @@
+//! Lógica de decisión sin FFI
+//!
+//! Qué emblemas lleva un archivo, qué acciones ofrece el menú para una selección
+//! y cómo se traduce una URI de Nautilus al path que entiende el daemon. Nada de
+//! este módulo toca GLib ni libnautilus: los callbacks `extern "C"` de
+//! `provider.rs` y `menu_provider.rs` leen los datos de Nautilus, llaman aquí y
+//! aplican el resultado.
+
+use crate::config::{Emblems, MenuItems};
+use crate::{FileStatusData, SyncStatus};
+use percent_encoding::percent_decode_str;
+use std::path::{Path, PathBuf};
+
+// ============================================================
+// URIs
+// ============================================================
+
+/// Path local de una URI file:// (decodificando el percent-encoding).
+///
+/// None para otros esquemas o si el path no es UTF-8 válido.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let decoded = percent_decode_str(encoded).decode_utf8().ok()?;
+    Some(Path::new(decoded.as_ref()).to_path_buf())
+}
+
+/// La URI es un archivo local dentro de alguna de las raíces
+pub fn is_under_roots(uri: &str, roots: &[PathBuf]) -> bool {
+    uri_to_path(uri).is_some_and(|path| roots.iter().any(|root| path.starts_with(root)))
+}
+
+/// URI del directorio que contiene a `uri`
+pub fn parent_uri(uri: &str) -> &str {
+    uri.trim_end_matches('/')
+        .rsplit_once('/')
+        .map_or(uri, |(parent, _)| parent)
+}
+
+/// Nombre del archivo (último segmento decodificado de la URI)
+pub fn display_name(uri: &str) -> String {
+    let segment = uri.trim_end_matches('/').rsplit('/').next().unwrap_or(uri);
+    percent_decode_str(segment).decode_utf8_lossy().into_owned()
+}
+
+// ============================================================
+// Emblemas
+// ============================================================
+
+/// Emblemas de un archivo, en el orden en que se añaden.
+///
+/// El de compartido va primero para que quede visualmente "abajo" del de estado
+/// (el último añadido queda arriba). `Unknown` no lleva emblema de estado.
+pub fn emblems_for(data: &FileStatusData, emblems: &Emblems) -> Vec<String> {
+    let mut names = Vec::with_capacity(2);
+    if data.is_shared {
+        names.push(emblems.shared.clone());
+    }
+
+    let status = match data.status {
+        // Verde: sincronizado (local + drive)
+        SyncStatus::Synced => Some(emblems.synced.clone()),
+        // Azul: solo en drive
+        SyncStatus::CloudOnly => Some(emblems.cloud_only.clone()),
+        // Naranja: solo local (pendiente de subir)
+        SyncStatus::LocalOnly => Some(emblems.local_only.clone()),
+        // Rojo: error
+        SyncStatus::Error => Some(emblems.error.clone()),
+        // Morado: conflicto entre local y drive
+        SyncStatus::Conflict => Some(emblems.conflict.clone()),
+        // Verde/azul: carpeta parcialmente disponible sin conexión
+        SyncStatus::Partial => Some(emblems.partial.clone()),
+        // Naranja con progreso: subiendo
+        SyncStatus::Uploading { percent } => {
+            Some(format!("{}-{}", emblems.uploading_prefix, progress_step(percent)))
+        }
+        // Azul con progreso: descargando
+        SyncStatus::Downloading { percent } => {
+            Some(format!("{}-{}", emblems.downloading_prefix, progress_step(percent)))
+        }
+        // Sin emblema
+        SyncStatus::Unknown => None,
+    };
+    names.extend(status);
+    names
+}
+
+/// Cuantiza el progreso a los emblemas disponibles (0, 25, 50, 75)
+pub fn progress_step(percent: u8) -> u8 {
+    (percent.min(99) / 25) * 25
+}
+
+// ============================================================
+// Menú contextual
+// ============================================================
+
+/// Máximo de items con el motivo del error en una selección múltiple
+pub const MAX_ERROR_DETAIL_ITEMS: usize = 3;
+
+/// Longitud máxima del motivo en la etiqueta (el completo va en el tooltip)
+pub const MAX_ERROR_LABEL_CHARS: usize = 60;
+
+/// Archivo seleccionado tal como lo ve el menú
+pub struct SelectedFile {
+    pub uri: String,
+    pub is_directory: bool,
+    /// None si el daemon no respondió
+    pub data: Option<FileStatusData>,
+}
+
+/// Archivo en error con su motivo (si el daemon lo conoce)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDetail {
+    pub uri: String,
+    pub reason: Option<String>,
+}
+
+/// Acciones del menú de una selección: cada lista son las URIs a las que se
+/// aplica; una lista vacía significa que el item no se muestra
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SelectionMenu {
+    /// Synced → liberar espacio
+    pub free_space: Vec<String>,
+    /// CloudOnly → mantener siempre local
+    pub keep_local: Vec<String>,
+    /// Conflict → submenú de resolución
+    pub resolve_conflict: Vec<String>,
+    /// Carpetas rastreadas → acciones recursivas
+    pub folders: Vec<String>,
+    /// Error → reintentar
+    pub retry_sync: Vec<String>,
+    /// Motivos informativos bajo "Reintentar" (como mucho `MAX_ERROR_DETAIL_ITEMS`)
+    pub error_details: Vec<ErrorDetail>,
+}
+
+impl SelectionMenu {
+    /// Clasifica la selección y descarta las acciones deshabilitadas en `[menu]`
+    pub fn build(files: impl IntoIterator<Item = SelectedFile>, enabled: &MenuItems) -> Self {
+        let mut menu = Self::default();
+        let mut errors = Vec::new();
+
+        for file in files {
+            let Some(data) = file.data else {
+                continue;
+            };
+            match data.status {
+                SyncStatus::Unknown => {}
+                _ if file.is_directory => menu.folders.push(file.uri),
+                SyncStatus::Synced => menu.free_space.push(file.uri),
+                SyncStatus::CloudOnly => menu.keep_local.push(file.uri),
+                SyncStatus::Conflict => menu.resolve_conflict.push(file.uri),
+                SyncStatus::Error => errors.push(ErrorDetail {
+                    uri: file.uri,
+                    reason: data.error_message,
+                }),
+                // Transferencias en curso, pendientes y parciales: sin acciones
+                _ => {}
+            }
+        }
+
+        if !enabled.free_space {
+            menu.free_space.clear();
+        }
+        if !enabled.keep_local {
+            menu.keep_local.clear();
+        }
+        if !enabled.resolve_conflict {
+            menu.resolve_conflict.clear();
+        }
+        if !enabled.folder_availability {
+            menu.folders.clear();
+        }
+        if enabled.retry_sync {
+            menu.retry_sync = errors.iter().map(|error| error.uri.clone()).collect();
+            errors.truncate(MAX_ERROR_DETAIL_ITEMS);
+            menu.error_details = errors;
+        }
+        menu
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free_space.is_empty()
+            && self.keep_local.is_empty()
+            && self.resolve_conflict.is_empty()
+            && self.folders.is_empty()
+            && self.retry_sync.is_empty()
+    }
+}
+
+/// Acciones del menú de fondo de una carpeta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundAction {
+    KeepFolderLocal,
+    FreeFolderSpace,
+    SyncNow,
+    PauseSync,
+}
+
+/// Acciones para el fondo de una carpeta; ninguna si el daemon no la rastrea
+pub fn background_actions(status: Option<SyncStatus>, enabled: &MenuItems) -> Vec<BackgroundAction> {
+    let mut actions = Vec::new();
+    match status {
+        None | Some(SyncStatus::Unknown) => return actions,
+        Some(_) => {}
+    }
+
+    if enabled.folder_availability {
+        actions.push(BackgroundAction::KeepFolderLocal);
+        actions.push(BackgroundAction::FreeFolderSpace);
+    }
+    if enabled.sync_now {
+        actions.push(BackgroundAction::SyncNow);
+    }
+    if enabled.pause_sync {
+        actions.push(BackgroundAction::PauseSync);
+    }
+    actions
+}
+
+/// Recorta el motivo de error para la etiqueta del menú
+pub fn truncate_label(text: &str) -> String {
+    if text.chars().count() <= MAX_ERROR_LABEL_CHARS {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(MAX_ERROR_LABEL_CHARS - 1).collect();
+        format!("{}…", truncated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileAvailability;
+
+    const ALL_STATUSES: [SyncStatus; 9] = [
+        SyncStatus::Synced,
+        SyncStatus::CloudOnly,
+        SyncStatus::LocalOnly,
+        SyncStatus::Error,
+        SyncStatus::Unknown,
+        SyncStatus::Uploading { percent: 40 },
+        SyncStatus::Downloading { percent: 80 },
+        SyncStatus::Conflict,
+        SyncStatus::Partial,
+    ];
+
+    /// El estado ya refleja la disponibilidad: los emblemas y el menú no deben
+    /// cambiar con ella, así que las tablas recorren todas las combinaciones
+    const ALL_AVAILABILITIES: [FileAvailability; 3] = [
+        FileAvailability::LocalOnline,
+        FileAvailability::OnlineOnly,
+        FileAvailability::NotTracked,
+    ];
+
+    fn data_with(status: SyncStatus, availability: FileAvailability, is_shared: bool) -> FileStatusData {
+        FileStatusData {
+            status,
+            availability,
+            is_shared,
+            ..FileStatusData::unknown()
+        }
+    }
+
+    fn data(status: SyncStatus, is_shared: bool) -> FileStatusData {
+        data_with(status, FileAvailability::LocalOnline, is_shared)
+    }
+
+    fn selected_with(uri: &str, status: SyncStatus, availability: FileAvailability, is_directory: bool) -> SelectedFile {
+        SelectedFile {
+            uri: uri.to_string(),
+            is_directory,
+            data: Some(data_with(status, availability, false)),
+        }
+    }
+
+    fn selected(uri: &str, status: SyncStatus, is_directory: bool) -> SelectedFile {
+        selected_with(uri, status, FileAvailability::LocalOnline, is_directory)
+    }
+
+    #[test]
+    fn emblems_for_every_status_availability_and_sharing() {
+        let emblems = Emblems::default();
+        for (status, availability) in ALL_STATUSES.into_iter().flat_map(|s| ALL_AVAILABILITIES.map(|a| (s, a))) {
+            let expected = match status {
+                SyncStatus::Synced => Some("emblem-gdrivexp-synced"),
+                SyncStatus::CloudOnly => Some("emblem-gdrivexp-cloud"),
+                SyncStatus::LocalOnly => Some("emblem-gdrivexp-local"),
+                SyncStatus::Error => Some("emblem-gdrivexp-error"),
+                SyncStatus::Conflict => Some("emblem-gdrivexp-conflict"),
+                SyncStatus::Partial => Some("emblem-gdrivexp-partial"),
+                SyncStatus::Uploading { .. } => Some("emblem-gdrivexp-upload-25"),
+                SyncStatus::Downloading { .. } => Some("emblem-gdrivexp-download-75"),
+                SyncStatus::Unknown => None,
+            };
+
+            let context = format!("{:?} {:?}", status, availability);
+            let plain: Vec<String> = expected.iter().map(|e| e.to_string()).collect();
+            assert_eq!(emblems_for(&data_with(status, availability, false), &emblems), plain, "{}", context);
+
+            // Compartido primero: el de estado queda encima
+            let mut shared = vec!["emblem-shared".to_string()];
+            shared.extend(plain);
+            assert_eq!(emblems_for(&data_with(status, availability, true), &emblems), shared, "{} shared", context);
+        }
+    }
+
+    #[test]
+    fn emblems_use_the_configured_names() {
+        let emblems = Emblems {
+            synced: "ok".to_string(),
+            shared: "compartido".to_string(),
+            uploading_prefix: "subiendo".to_string(),
+            ..Emblems::default()
+        };
+        assert_eq!(emblems_for(&data(SyncStatus::Synced, true), &emblems), ["compartido", "ok"]);
+        assert_eq!(
+            emblems_for(&data(SyncStatus::Uploading { percent: 0 }, false), &emblems),
+            ["subiendo-0"]
+        );
+    }
+
+    #[test]
+    fn progress_steps() {
+        for (percent, step) in [(0, 0), (24, 0), (25, 25), (49, 25), (50, 50), (75, 75), (99, 75), (100, 75), (255, 75)] {
+            assert_eq!(progress_step(percent), step, "{}%", percent);
+        }
+    }
+
+    /// Lista del menú que recibe un archivo (None: ningún item)
+    fn expected_list(status: SyncStatus, is_directory: bool) -> Option<&'static str> {
+        match status {
+            SyncStatus::Unknown => None,
+            _ if is_directory => Some("folders"),
+            SyncStatus::Synced => Some("free_space"),
+            SyncStatus::CloudOnly => Some("keep_local"),
+            SyncStatus::Conflict => Some("resolve_conflict"),
+            SyncStatus::Error => Some("retry_sync"),
+            _ => None,
+        }
+    }
+
+    fn lists(menu: &SelectionMenu) -> [(&'static str, &Vec<String>); 5] {
+        [
+            ("free_space", &menu.free_space),
+            ("keep_local", &menu.keep_local),
+            ("resolve_conflict", &menu.resolve_conflict),
+            ("folders", &menu.folders),
+            ("retry_sync", &menu.retry_sync),
+        ]
+    }
+
+    /// Todas las acciones habilitadas, y cada una deshabilitada por separado
+    fn menu_configs() -> Vec<(Option<&'static str>, MenuItems)> {
+        let all = MenuItems::default();
+        vec![
+            (None, all),
+            (Some("free_space"), MenuItems { free_space: false, ..all }),
+            (Some("keep_local"), MenuItems { keep_local: false, ..all }),
+            (Some("resolve_conflict"), MenuItems { resolve_conflict: false, ..all }),
+            (Some("retry_sync"), MenuItems { retry_sync: false, ..all }),
+            (Some("folders"), MenuItems { folder_availability: false, ..all }),
+            // No afectan al menú de la selección
+            (None, MenuItems { sync_now: false, pause_sync: false, ..all }),
+        ]
+    }
+
+    #[test]
+    fn selection_menu_for_every_status_availability_directory_and_flag() {
+        let uri = "file:///home/ana/Drive/x";
+        for (status, availability) in ALL_STATUSES.into_iter().flat_map(|s| ALL_AVAILABILITIES.map(|a| (s, a))) {
+            for is_directory in [false, true] {
+                for (disabled, enabled) in menu_configs() {
+                    let menu = SelectionMenu::build([selected_with(uri, status, availability, is_directory)], &enabled);
+                    let expected = expected_list(status, is_directory).filter(|list| Some(*list) != disabled);
+                    let context =
+                        format!("{:?} {:?} dir={} disabled={:?}", status, availability, is_directory, disabled);
+
+                    for (name, list) in lists(&menu) {
+                        let want: &[&str] = if Some(name) == expected { &[uri] } else { &[] };
+                        assert_eq!(list, want, "{} in {}", name, context);
+                    }
+                    assert_eq!(menu.is_empty(), expected.is_none(), "{}", context);
+                    assert_eq!(menu.error_details.len(), usize::from(expected == Some("retry_sync")), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn selection_menu_skips_files_without_an_answer() {
+        let file = SelectedFile {
+            uri: "file:///a".to_string(),
+            is_directory: false,
+            data: None,
+        };
+        assert!(SelectionMenu::build([file], &MenuItems::default()).is_empty());
+    }
+
+    #[test]
+    fn selection_menu_groups_a_mixed_selection() {
+        let menu = SelectionMenu::build(
+            [
+                selected("file:///a", SyncStatus::Synced, false),
+                selected_with("file:///b", SyncStatus::CloudOnly, FileAvailability::OnlineOnly, false),
+                selected("file:///c", SyncStatus::Synced, false),
+                selected("file:///d", SyncStatus::Synced, true),
+            ],
+            &MenuItems::default(),
+        );
+        assert_eq!(menu.free_space, ["file:///a", "file:///c"]);
+        assert_eq!(menu.keep_local, ["file:///b"]);
+        assert_eq!(menu.folders, ["file:///d"]);
+    }
+
+    #[test]
+    fn error_details_are_truncated_but_every_file_is_retried() {
+        let files = (0..MAX_ERROR_DETAIL_ITEMS + 2).map(|i| SelectedFile {
+            uri: format!("file:///e{}", i),
+            is_directory: false,
+            data: Some(FileStatusData {
+                error_message: (i != 1).then(|| format!("motivo {}", i)),
+                ..data(SyncStatus::Error, false)
+            }),
+        });
+        let menu = SelectionMenu::build(files, &MenuItems::default());
+
+        assert_eq!(menu.retry_sync.len(), MAX_ERROR_DETAIL_ITEMS + 2);
+        assert_eq!(menu.error_details.len(), MAX_ERROR_DETAIL_ITEMS);
+        assert_eq!(
+            menu.error_details[..2],
+            [
+                ErrorDetail { uri: "file:///e0".to_string(), reason: Some("motivo 0".to_string()) },
+                ErrorDetail { uri: "file:///e1".to_string(), reason: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn background_actions_per_status_and_flags() {
+        use BackgroundAction::*;
+        let all = MenuItems::default();
+        assert!(background_actions(None, &all).is_empty());
+        assert!(background_actions(Some(SyncStatus::Unknown), &all).is_empty());
+        for status in ALL_STATUSES.into_iter().filter(|s| *s != SyncStatus::Unknown) {
+            assert_eq!(
+                background_actions(Some(status), &all),
+                [KeepFolderLocal, FreeFolderSpace, SyncNow, PauseSync],
+                "{:?}",
+                status
+            );
+        }
+
+        let synced = Some(SyncStatus::Synced);
+        let without_folders = MenuItems { folder_availability: false, ..all };
+        assert_eq!(background_actions(synced, &without_folders), [SyncNow, PauseSync]);
+        let without_sync = MenuItems { sync_now: false, pause_sync: false, ..all };
+        assert_eq!(background_actions(synced, &without_sync), [KeepFolderLocal, FreeFolderSpace]);
+        // Las acciones de la selección no cuentan aquí
+        let without_files = MenuItems { free_space: false, keep_local: false, resolve_conflict: false, retry_sync: false, ..all };
+        assert_eq!(background_actions(synced, &without_files).len(), 4);
+    }
+
+    #[test]
+    fn uri_to_path_decodes_local_uris() {
+        assert_eq!(uri_to_path("file:///home/ana/a%20b/%C3%B1.txt"), Some(PathBuf::from("/home/ana/a b/ñ.txt")));
+        assert_eq!(uri_to_path("file:///"), Some(PathBuf::from("/")));
+        assert_eq!(uri_to_path("sftp://host/a"), None);
+        assert_eq!(uri_to_path("/home/ana/a"), None);
+        // UTF-8 inválido
+        assert_eq!(uri_to_path("file:///a/%FF"), None);
+    }
+
+    #[test]
+    fn is_under_roots_compares_whole_components() {
+        let roots = [PathBuf::from("/home/ana/Drive"), PathBuf::from("/mnt/Equipo")];
+        assert!(is_under_roots("file:///home/ana/Drive", &roots));
+        assert!(is_under_roots("file:///home/ana/Drive/a.txt", &roots));
+        assert!(is_under_roots("file:///mnt/Equipo/x/y", &roots));
+        assert!(!is_under_roots("file:///home/ana/DriveBackup/a.txt", &roots));
+        assert!(!is_under_roots("file:///home/ana", &roots));
+        assert!(!is_under_roots("trash:///home/ana/Drive/a.txt", &roots));
+        assert!(!is_under_roots("file:///home/ana/Drive/a.txt", &[]));
+    }
+
+    #[test]
+    fn parent_uri_strips_the_last_segment() {
+        assert_eq!(parent_uri("file:///home/ana/a.txt"), "file:///home/ana");
+        assert_eq!(parent_uri("file:///home/ana/dir/"), "file:///home/ana");
+        assert_eq!(parent_uri("file:///a"), "file://");
+        assert_eq!(parent_uri("sin-barras"), "sin-barras");
+    }
+
+    #[test]
+    fn display_name_decodes_the_last_segment() {
+        assert_eq!(display_name("file:///home/ana/informe%20final.pdf"), "informe final.pdf");
+        assert_eq!(display_name("file:///home/ana/Fotos/"), "Fotos");
+        assert_eq!(display_name("file:///home/ana/%C3%B1"), "ñ");
+        assert_eq!(display_name("a.txt"), "a.txt");
+    }
+
+    #[test]
+    fn truncate_label_counts_characters() {
+        let exact = "a".repeat(MAX_ERROR_LABEL_CHARS);
+        assert_eq!(truncate_label(&exact), exact);
+
+        let long = "ñ".repeat(MAX_ERROR_LABEL_CHARS + 1);
+        let truncated = truncate_label(&long);
+        assert_eq!(truncated.chars().count(), MAX_ERROR_LABEL_CHARS);
+        assert!(truncated.ends_with("ñ…"));
+        assert_eq!(truncate_label(""), "");
+    }
+}
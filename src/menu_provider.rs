@@ -1,32 +1,29 @@
 //! Implementación del MenuProvider para acciones contextuales
 
 use crate::ffi::*;
+use crate::folder_summary::{self, FolderImpact};
+use crate::i18n::{fill, tr, trn};
 use crate::ipc_client::IpcClient;
-use crate::SyncStatus;
+use crate::logic::{self, BackgroundAction};
+use crate::FileAvailability;
+use gdrivexp_ipc_proto::{AvailabilityImpact, ConflictResolution};
 use glib_sys::gpointer;
 use gobject_sys::GObject;
-use percent_encoding::percent_decode_str;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::ops::AsyncFn;
+use std::sync::{Condvar, Mutex, OnceLock};
 use std::thread;
-
-// Helper de logging
-fn log_debug(msg: &str) {
-    eprintln!("G-DriveXP-Ext: {}", msg);
-    use std::io::Write;
-    if let Ok(mut file) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("/tmp/gdrivexp-nautilus-v3.log")
-    {
-        let _ = writeln!(file, "{}", msg);
-    }
-}
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 /// Callback para get_file_items (soporta selección múltiple)
 pub unsafe extern "C" fn get_file_items_impl(
     _provider: *mut GObject,
     files: *mut glib_sys::GList,
 ) -> *mut glib_sys::GList {
-    log_debug("v4: get_file_items_impl called");
+    log_debug!("get_file_items_impl called");
 
     if files.is_null() {
         return std::ptr::null_mut();
@@ -37,80 +34,371 @@ pub unsafe extern "C" fn get_file_items_impl(
         return std::ptr::null_mut();
     }
 
-    log_debug(&format!("v4: file_count = {}", file_count));
-
-    // 1. Recolectar URIs de todos los archivos seleccionados
-    let mut free_uris: Vec<String> = Vec::new();   // Synced → pueden liberar espacio
-    let mut download_uris: Vec<String> = Vec::new(); // CloudOnly → pueden descargar
+    log_debug!("file_count = {}", file_count);
 
+    // 1. Estado de cada archivo seleccionado
+    let mut selection = Vec::new();
     let mut node = files;
     while !node.is_null() {
         let file = (*node).data as *mut NautilusFileInfo;
         let uri_ptr = nautilus_file_info_get_uri(file);
-        if let Some(uri) = gchar_to_string_free(uri_ptr) {
-            if uri.starts_with("file://") {
-                let path_str = uri.strip_prefix("file://").unwrap_or(&uri);
-                // Validar que el path se puede decodificar
-                if percent_decode_str(path_str).decode_utf8().is_ok() {
-                    match crate::provider::ipc_query_status(&uri).ok() {
-                        Some(SyncStatus::Synced) => {
-                            log_debug(&format!("v4: {} -> Synced (can free)", uri));
-                            free_uris.push(uri);
-                        }
-                        Some(SyncStatus::CloudOnly) => {
-                            log_debug(&format!("v4: {} -> CloudOnly (can download)", uri));
-                            download_uris.push(uri);
-                        }
-                        other => {
-                            log_debug(&format!("v4: {} -> {:?} (skip)", uri, other));
-                        }
-                    }
-                }
-            }
+        // Sólo archivos locales con un path decodificable
+        if let Some(uri) = gchar_to_string_free(uri_ptr).filter(|uri| logic::uri_to_path(uri).is_some()) {
+            let is_directory = nautilus_file_info_is_directory(file) != glib_sys::GFALSE;
+            let data = crate::provider::ipc_query_extended_status(&uri, is_directory).ok();
+            log_debug!("{} -> {:?}", uri, data.as_ref().map(|d| d.status));
+            selection.push(logic::SelectedFile { uri, is_directory, data });
         }
         node = (*node).next;
     }
 
-    // 2. Construir menú según los estados encontrados
+    // 2. Construir menú según los estados encontrados (y las acciones habilitadas)
+    let menu = logic::SelectionMenu::build(selection, &crate::config::get().menu);
+    if menu.is_empty() {
+        return std::ptr::null_mut();
+    }
     let mut items: *mut glib_sys::GList = std::ptr::null_mut();
 
-    if !free_uris.is_empty() {
-        log_debug(&format!("v4: Showing 'Liberar espacio' for {} files", free_uris.len()));
+    if !menu.free_space.is_empty() {
+        log_debug!("Showing 'Liberar espacio' for {} files", menu.free_space.len());
         let item = create_menu_item(
             "gdrivexp::free_space",
-            "Liberar espacio",
-            "Eliminar copia local, mantener en la nube",
+            &tr("Free up space"),
+            &tr("Remove the local copy, keep it in the cloud"),
             "weather-few-clouds-symbolic",
         );
 
-        let uris_boxed = Box::into_raw(Box::new(free_uris)) as gpointer;
+        let uris_boxed = Box::into_raw(Box::new(menu.free_space)) as gpointer;
         connect_activate(item, free_space_callback, uris_boxed);
         items = g_list_append(items, item as gpointer);
     }
 
-    if !download_uris.is_empty() {
-        log_debug(&format!("v4: Showing 'Mantener siempre local' for {} files", download_uris.len()));
+    if !menu.keep_local.is_empty() {
+        log_debug!("Showing 'Mantener siempre local' for {} files", menu.keep_local.len());
         let item = create_menu_item(
             "gdrivexp::keep_local",
-            "Mantener siempre local",
-            "Descargar y mantener copia local",
+            &tr("Always keep on this device"),
+            &tr("Download and keep a local copy"),
             "folder-download-symbolic",
         );
 
-        let uris_boxed = Box::into_raw(Box::new(download_uris)) as gpointer;
+        let uris_boxed = Box::into_raw(Box::new(menu.keep_local)) as gpointer;
         connect_activate(item, keep_local_callback, uris_boxed);
         items = g_list_append(items, item as gpointer);
     }
 
+    if !menu.resolve_conflict.is_empty() {
+        log_debug!("Showing 'Resolver conflicto' for {} files", menu.resolve_conflict.len());
+        let item = create_menu_item(
+            "gdrivexp::resolve_conflict",
+            &tr("Resolve conflict"),
+            &tr("Choose which version to keep"),
+            "dialog-warning-symbolic",
+        );
+
+        let submenu = nautilus_menu_new();
+        let choices: [(&str, String, String, unsafe extern "C" fn(*mut NautilusMenuItem, gpointer)); 3] = [
+            (
+                "gdrivexp::conflict_keep_local",
+                tr("Keep local version"),
+                tr("Upload the local version and discard the Drive one"),
+                keep_local_version_callback,
+            ),
+            (
+                "gdrivexp::conflict_keep_remote",
+                tr("Keep Drive version"),
+                tr("Download the Drive version and discard the local one"),
+                keep_remote_version_callback,
+            ),
+            (
+                "gdrivexp::conflict_keep_both",
+                tr("Keep both"),
+                tr("Save the local version as a conflicted copy"),
+                keep_both_versions_callback,
+            ),
+        ];
+        for (name, label, tip, callback) in choices {
+            let choice = create_menu_item(name, &label, &tip, "");
+            let uris_boxed = Box::into_raw(Box::new(menu.resolve_conflict.clone())) as gpointer;
+            connect_activate(choice, callback, uris_boxed);
+            nautilus_menu_append_item(submenu, choice);
+            gobject_sys::g_object_unref(choice as *mut GObject);
+        }
+        nautilus_menu_item_set_submenu(item, submenu);
+        gobject_sys::g_object_unref(submenu as *mut GObject);
+
+        items = g_list_append(items, item as gpointer);
+    }
+
+    if !menu.folders.is_empty() {
+        log_debug!("Showing recursive folder actions for {} folders", menu.folders.len());
+        let (download_impact, free_impact) = preview_folder_impact(&menu.folders);
+
+        let item = create_menu_item(
+            "gdrivexp::folder_keep_local",
+            &format!("{}{}", tr("Always keep entire folder on this device"), impact_suffix(download_impact)),
+            &tr("Download all of the folder's contents and keep a local copy"),
+            "folder-download-symbolic",
+        );
+        let uris_boxed = Box::into_raw(Box::new(menu.folders.clone())) as gpointer;
+        connect_activate(item, keep_folder_local_callback, uris_boxed);
+        items = g_list_append(items, item as gpointer);
+
+        let item = create_menu_item(
+            "gdrivexp::folder_free_space",
+            &format!("{}{}", tr("Free up space for entire folder"), impact_suffix(free_impact)),
+            &tr("Remove the local copies of all the contents, keep them in the cloud"),
+            "weather-few-clouds-symbolic",
+        );
+        let uris_boxed = Box::into_raw(Box::new(menu.folders)) as gpointer;
+        connect_activate(item, free_folder_space_callback, uris_boxed);
+        items = g_list_append(items, item as gpointer);
+    }
+
+    if !menu.retry_sync.is_empty() {
+        log_debug!("Showing 'Reintentar sincronización' for {} files", menu.retry_sync.len());
+        let item = create_menu_item(
+            "gdrivexp::retry_sync",
+            &tr("Retry sync"),
+            &tr("Sync the files with errors again"),
+            "view-refresh-symbolic",
+        );
+
+        let single = menu.retry_sync.len() == 1;
+        let uris_boxed = Box::into_raw(Box::new(menu.retry_sync)) as gpointer;
+        connect_activate(item, retry_sync_callback, uris_boxed);
+        items = g_list_append(items, item as gpointer);
+
+        // Motivo del error, informativo (no activable)
+        for (index, detail) in menu.error_details.iter().enumerate() {
+            let reason = detail.reason.clone().unwrap_or_else(|| tr("unknown reason"));
+            let label = if single {
+                fill(&tr("Error: {}"), &[&logic::truncate_label(&reason)])
+            } else {
+                format!("{}: {}", logic::display_name(&detail.uri), logic::truncate_label(&reason))
+            };
+
+            let item = create_menu_item(
+                &format!("gdrivexp::error_detail_{}", index),
+                &label,
+                &reason,
+                "dialog-error-symbolic",
+            );
+            set_sensitive(item, false);
+            items = g_list_append(items, item as gpointer);
+        }
+    }
+
+    items
+}
+
+/// Callback para get_background_items (clic derecho en el fondo de una carpeta)
+///
+/// # Safety
+///
+/// Llamada por Nautilus en el main thread con un `current_folder` válido o NULL.
+pub unsafe extern "C" fn get_background_items_impl(
+    _provider: *mut GObject,
+    current_folder: *mut NautilusFileInfo,
+) -> *mut glib_sys::GList {
+    log_debug!("get_background_items_impl called");
+
+    if current_folder.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let uri = match gchar_to_string_free(nautilus_file_info_get_uri(current_folder)) {
+        Some(u) if u.starts_with("file://") => u,
+        _ => return std::ptr::null_mut(),
+    };
+
+    // Solo carpetas dentro del montaje de G-DriveXP (el daemon las rastrea)
+    let status = crate::provider::ipc_query_status(&uri, true).ok();
+    log_debug!("background {} -> {:?}", uri, status);
+
+    let mut items: *mut glib_sys::GList = std::ptr::null_mut();
+    for action in logic::background_actions(status, &crate::config::get().menu) {
+        let (item, callback): (_, unsafe extern "C" fn(*mut NautilusMenuItem, gpointer)) = match action {
+            BackgroundAction::KeepFolderLocal => (
+                create_menu_item(
+                    "gdrivexp::bg_keep_local",
+                    &tr("Always keep this folder on this device"),
+                    &tr("Download the folder's contents and keep a local copy"),
+                    "folder-download-symbolic",
+                ),
+                keep_folder_local_callback,
+            ),
+            BackgroundAction::FreeFolderSpace => (
+                create_menu_item(
+                    "gdrivexp::bg_free_space",
+                    &tr("Free up space in this folder"),
+                    &tr("Remove the folder's local copies, keep them in the cloud"),
+                    "weather-few-clouds-symbolic",
+                ),
+                free_folder_space_callback,
+            ),
+            BackgroundAction::SyncNow => (
+                create_menu_item(
+                    "gdrivexp::bg_sync_now",
+                    &tr("Sync now"),
+                    &tr("Sync this folder with Google Drive right away"),
+                    "emblem-synchronizing-symbolic",
+                ),
+                sync_now_callback,
+            ),
+            BackgroundAction::PauseSync => (
+                create_menu_item(
+                    "gdrivexp::bg_pause_sync",
+                    &tr("Pause syncing this folder"),
+                    &tr("Stop syncing this folder until it is resumed"),
+                    "media-playback-pause-symbolic",
+                ),
+                pause_sync_callback,
+            ),
+        };
+        connect_activate(item, callback, folder_user_data(&uri));
+        items = g_list_append(items, item as gpointer);
+    }
+
     items
 }
 
 // === Helpers ===
 
-// Helper Runtime removed
+unsafe fn set_sensitive(item: *mut NautilusMenuItem, sensitive: bool) {
+    let property = str_to_cstring("sensitive");
+    gobject_sys::g_object_set(
+        item as *mut GObject,
+        property.as_ptr(),
+        sensitive as glib_sys::gboolean,
+        std::ptr::null::<std::os::raw::c_char>(),
+    );
+}
+
+/// Alcance de descargar / liberar las carpetas, sumado; None si alguna no se conoce.
+///
+/// Las carpetas sin alcance en caché se encargan al hilo de simulación y se
+/// espera su respuesta como mucho `query_timeout` (el mismo límite que las
+/// consultas de estado), así que el primer clic ya muestra el recuento. Si el
+/// daemon tarda más, el menú sale sin recuento y el siguiente clic lo tendrá.
+fn preview_folder_impact(uris: &[String]) -> (Option<AvailabilityImpact>, Option<AvailabilityImpact>) {
+    let missing: Vec<&String> = uris.iter().filter(|uri| folder_summary::lookup_impact(uri).is_none()).collect();
+    if !missing.is_empty() {
+        for uri in &missing {
+            request_folder_impact(uri);
+        }
+        wait_folder_impact(&missing, crate::config::get().query_timeout);
+    }
+
+    let mut download = Some(AvailabilityImpact::default());
+    let mut free = Some(AvailabilityImpact::default());
+    for uri in uris {
+        let impact = folder_summary::lookup_impact(uri);
+        download = sum_impact(download, impact.and_then(|impact| impact.download));
+        free = sum_impact(free, impact.and_then(|impact| impact.free));
+    }
+    (download, free)
+}
+
+/// Espera a que el hilo de simulación termine con `uris` o venza `timeout`
+fn wait_folder_impact(uris: &[&String], timeout: Duration) {
+    let Ok(pending) = IMPACT_PENDING.lock() else { return };
+    let _ = IMPACT_DONE.wait_timeout_while(pending, timeout, |pending| {
+        uris.iter().any(|uri| pending.contains(uri.as_str()))
+    });
+}
+
+fn sum_impact(total: Option<AvailabilityImpact>, impact: Option<AvailabilityImpact>) -> Option<AvailabilityImpact> {
+    let (total, impact) = (total?, impact?);
+    Some(AvailabilityImpact {
+        files: total.files + impact.files,
+        bytes: total.bytes + impact.bytes,
+    })
+}
+
+/// Carpetas encargadas al hilo de simulación y aún sin respuesta
+static IMPACT_PENDING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Se notifica cada vez que una carpeta sale de `IMPACT_PENDING`
+static IMPACT_DONE: Condvar = Condvar::new();
+
+static IMPACT_REQUESTS: OnceLock<mpsc::UnboundedSender<String>> = OnceLock::new();
+
+/// Encarga la simulación de una carpeta (una sola vez mientras esté pendiente)
+fn request_folder_impact(uri: &str) {
+    if !IMPACT_PENDING.lock().is_ok_and(|mut pending| pending.insert(uri.to_string())) {
+        return;
+    }
+    let requests = IMPACT_REQUESTS.get_or_init(spawn_impact_thread);
+    if requests.send(uri.to_string()).is_err() {
+        // El hilo murió: permitir que se vuelva a pedir
+        if let Ok(mut pending) = IMPACT_PENDING.lock() {
+            pending.remove(uri);
+        }
+    }
+}
+
+/// Hilo con una única conexión que calcula el alcance de las carpetas encargadas
+fn spawn_impact_thread() -> mpsc::UnboundedSender<String> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let client = IpcClient::new();
+            while let Some(uri) = rx.recv().await {
+                if folder_summary::lookup_impact(&uri).is_none() {
+                    match folder_impact(&client, &uri).await {
+                        Ok(impact) => folder_summary::store_impact(&uri, impact),
+                        Err(e) => log_debug!("Impact preview failed for {}: {}", uri, e),
+                    }
+                }
+                if let Ok(mut pending) = IMPACT_PENDING.lock() {
+                    pending.remove(&uri);
+                }
+                IMPACT_DONE.notify_all();
+            }
+        });
+    });
+
+    tx
+}
 
+/// Las dos simulaciones (descargar y liberar) de una carpeta
+async fn folder_impact(client: &IpcClient, uri: &str) -> std::io::Result<FolderImpact> {
+    let dry_run = |availability| async move {
+        tokio::time::timeout(
+            crate::config::get().daemon_timeout,
+            client.set_availability_recursive(uri, availability, true),
+        )
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "impact preview timed out"))?
+    };
+    Ok(FolderImpact {
+        download: dry_run(FileAvailability::LocalOnline).await?,
+        free: dry_run(FileAvailability::OnlineOnly).await?,
+    })
+}
 
-// IPC Client helper removed (now instantiated per thread)
+/// " (12 archivos, 340 MB)" o vacío si no se conoce el alcance
+fn impact_suffix(impact: Option<AvailabilityImpact>) -> String {
+    match impact {
+        Some(impact) => fill(
+            &trn(" ({} file, {})", " ({} files, {})", impact.files),
+            &[&impact.files, &crate::properties_provider::format_size(impact.bytes)],
+        ),
+        None => String::new(),
+    }
+}
+
+/// user_data para los callbacks: la carpeta como lista de un solo elemento
+fn folder_user_data(uri: &str) -> gpointer {
+    Box::into_raw(Box::new(vec![uri.to_string()])) as gpointer
+}
 
 unsafe fn create_menu_item(
     name: &str,
@@ -137,7 +425,7 @@ unsafe fn connect_activate(
     user_data: gpointer,
 ) {
     let signal = str_to_cstring("activate");
-    log_debug(&format!("v3: Connecting signal 'activate' to item {:?} with user_data {:?}", item, user_data));
+    log_trace!("Connecting signal 'activate' to item {:?} with user_data {:?}", item, user_data);
     
     // Transmute callback to generic function pointer
     let cb_ptr: unsafe extern "C" fn() = std::mem::transmute(callback);
@@ -151,32 +439,44 @@ unsafe fn connect_activate(
         0, // G_CONNECT_DEFAULT
     );
     
-    log_debug(&format!("v3: g_signal_connect_data returned handler_id: {}", handler_id));
+    log_trace!("g_signal_connect_data returned handler_id: {}", handler_id);
 }
 
 unsafe extern "C" fn free_user_data(data: gpointer, _closure: *mut gobject_sys::GClosure) {
     if !data.is_null() {
         let ptr = data as *mut Vec<String>;
-        log_debug(&format!("v4: free_user_data called for {:?}", ptr));
+        log_trace!("free_user_data called for {:?}", ptr);
         drop(Box::from_raw(ptr));
     } else {
-        log_debug("v4: free_user_data called with NULL");
+        log_trace!("free_user_data called with NULL");
     }
 }
 
 // === Callbacks de Acción ===
 
-unsafe extern "C" fn free_space_callback(
-    _item: *mut NautilusMenuItem,
-    user_data: gpointer,
-) {
-    if user_data.is_null() { return; }
+/// URIs asociadas al item activado (vacío si no hay user_data)
+unsafe fn action_uris(user_data: gpointer) -> Vec<String> {
+    if user_data.is_null() {
+        return Vec::new();
+    }
+    unsafe { &*(user_data as *const Vec<String>) }.clone()
+}
 
-    let uris = unsafe { &*(user_data as *const Vec<String>) }.clone();
+/// Ejecuta una acción del menú en un hilo propio para no bloquear la UI de Nautilus.
+///
+/// `action` se llama una vez por objetivo; sólo `Ok(true)` cuenta como aplicada,
+/// `Ok(false)` (el daemon no la aplicó) y `Err` se registran como fallos.
+fn spawn_action<T, A>(label: String, targets: Vec<T>, action: A)
+where
+    T: fmt::Debug + Send + 'static,
+    A: AsyncFn(&IpcClient, &T) -> io::Result<bool> + Send + 'static,
+{
+    if targets.is_empty() {
+        return;
+    }
 
-    // Spawn thread to avoid blocking Nautilus UI
     thread::spawn(move || {
-        log_debug(&format!("v4: Action Thread Started: Free space for {} files", uris.len()));
+        log_debug!("Action Thread Started: {} for {} targets", label, targets.len());
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
@@ -184,40 +484,115 @@ unsafe extern "C" fn free_space_callback(
 
         rt.block_on(async {
             let client = IpcClient::new();
-            for uri in &uris {
-                match client.set_online_only(uri).await {
-                    Ok(_) => log_debug(&format!("IPC Success: Set Online Only for {}", uri)),
-                    Err(e) => log_debug(&format!("IPC Error for {}: {:?}", uri, e)),
+            for target in &targets {
+                match action(&client, target).await {
+                    Ok(true) => log_debug!("IPC Success: {} for {:?}", label, target),
+                    Ok(false) => log_warn!("{} not applied for {:?}", label, target),
+                    Err(e) => log_warn!("{} failed for {:?}: {}", label, target, e),
                 }
             }
         });
     });
 }
 
+unsafe extern "C" fn free_space_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    spawn_action("Set Online Only".into(), action_uris(user_data), async |client, uri| {
+        client.set_online_only(uri).await
+    });
+}
+
 unsafe extern "C" fn keep_local_callback(
     _item: *mut NautilusMenuItem,
     user_data: gpointer,
 ) {
-    if user_data.is_null() { return; }
+    spawn_action("Set Local Online".into(), action_uris(user_data), async |client, uri| {
+        client.set_local_online(uri).await
+    });
+}
 
-    let uris = unsafe { &*(user_data as *const Vec<String>) }.clone();
+unsafe extern "C" fn sync_now_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    spawn_action("Sync Now".into(), action_uris(user_data), async |client, uri| {
+        client.sync_now(uri).await
+    });
+}
 
-    // Spawn thread to avoid blocking Nautilus UI
-    thread::spawn(move || {
-        log_debug(&format!("v4: Action Thread Started: Keep local for {} files", uris.len()));
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .unwrap();
+unsafe extern "C" fn pause_sync_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    spawn_action("Pause Sync".into(), action_uris(user_data), async |client, uri| {
+        client.pause_sync(uri).await
+    });
+}
 
-        rt.block_on(async {
-            let client = IpcClient::new();
-            for uri in &uris {
-                match client.set_local_online(uri).await {
-                    Ok(_) => log_debug(&format!("IPC Success: Set Local Online for {}", uri)),
-                    Err(e) => log_debug(&format!("IPC Error for {}: {:?}", uri, e)),
-                }
-            }
-        });
+unsafe extern "C" fn keep_local_version_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    resolve_conflicts(user_data, ConflictResolution::KeepLocal);
+}
+
+unsafe extern "C" fn keep_remote_version_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    resolve_conflicts(user_data, ConflictResolution::KeepRemote);
+}
+
+unsafe extern "C" fn keep_both_versions_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    resolve_conflicts(user_data, ConflictResolution::KeepBoth);
+}
+
+unsafe fn resolve_conflicts(user_data: gpointer, resolution: ConflictResolution) {
+    let label = format!("Resolve Conflict ({:?})", resolution);
+    spawn_action(label, action_uris(user_data), async move |client, uri| {
+        client.resolve_conflict(uri, resolution).await
+    });
+}
+
+unsafe extern "C" fn retry_sync_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    // Un único RetrySync con toda la selección
+    let uris = action_uris(user_data);
+    let batch = if uris.is_empty() { Vec::new() } else { vec![uris] };
+    spawn_action("Retry Sync".into(), batch, async |client, uris| {
+        client.retry_sync(uris).await
+    });
+}
+
+unsafe extern "C" fn keep_folder_local_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    set_folders_availability(user_data, FileAvailability::LocalOnline);
+}
+
+unsafe extern "C" fn free_folder_space_callback(
+    _item: *mut NautilusMenuItem,
+    user_data: gpointer,
+) {
+    set_folders_availability(user_data, FileAvailability::OnlineOnly);
+}
+
+unsafe fn set_folders_availability(user_data: gpointer, availability: FileAvailability) {
+    let label = format!("Set {:?} recursively", availability);
+    spawn_action(label, action_uris(user_data), async move |client, uri| {
+        let impact = client.set_availability_recursive(uri, availability, false).await?;
+        match impact {
+            Some(impact) => log_debug!("{} files, {} bytes under {}", impact.files, impact.bytes, uri),
+            None => log_debug!("{} changed without recursion (daemon without support)", uri),
+        }
+        Ok(true)
     });
 }
@@ -0,0 +1,61 @@
+//! Puntos de montaje de G-DriveXP
+//!
+//! El daemon informa de sus raíces con `GetMountPoints` en cada conexión. Mientras
+//! se conozcan, los archivos fuera de ellas se descartan sin ningún tráfico IPC.
+
+use crate::logic;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+enum MountState {
+    /// Aún no hubo conexión con el daemon
+    Unknown,
+    /// Daemon sin `GetMountPoints`: se consulta cualquier archivo
+    Unsupported,
+    Roots(Vec<PathBuf>),
+}
+
+static STATE: RwLock<MountState> = RwLock::new(MountState::Unknown);
+
+/// Sustituye las raíces conocidas (llamado tras cada conexión con el daemon)
+pub fn update(roots: Vec<String>) {
+    log_info!("Mount points: {:?}", roots);
+    if let Ok(mut state) = STATE.write() {
+        *state = MountState::Roots(roots.into_iter().map(PathBuf::from).collect());
+    }
+}
+
+/// El daemon conectado no soporta `GetMountPoints`
+pub fn mark_unsupported() {
+    if let Ok(mut state) = STATE.write() {
+        *state = MountState::Unsupported;
+    }
+}
+
+/// true sólo si se sabe con certeza que la URI queda fuera de todo montaje.
+///
+/// Mientras no se conozcan las raíces devuelve false, para que la consulta
+/// llegue al daemon (y la conexión las obtenga).
+pub fn is_outside(uri: &str) -> bool {
+    let Ok(state) = STATE.read() else {
+        return false;
+    };
+    let MountState::Roots(roots) = &*state else {
+        return false;
+    };
+
+    // No es file:// o no se puede decodificar: nunca está en el montaje
+    !logic::is_under_roots(uri, roots)
+}
+
+/// true sólo si se sabe con certeza que la URI está dentro de un montaje
+pub fn is_inside(uri: &str) -> bool {
+    let Ok(state) = STATE.read() else {
+        return false;
+    };
+    let MountState::Roots(roots) = &*state else {
+        return false;
+    };
+
+    logic::is_under_roots(uri, roots)
+}
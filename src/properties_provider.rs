@@ -0,0 +1,161 @@
+//! Implementación del PropertiesModelProvider: sección "Google Drive" en Propiedades
+//!
+//! La sección se devuelve vacía de inmediato y se rellena cuando llega la
+//! respuesta de `GetFileDetails`, para no bloquear la apertura del diálogo.
+
+use crate::ffi::*;
+use crate::i18n::{fill, tr};
+use crate::ipc_client::IpcClient;
+use crate::{FileAvailability, SyncStatus};
+use gdrivexp_ipc_proto::FileDetails;
+use glib_sys::gpointer;
+use gobject_sys::GObject;
+use std::thread;
+
+/// GListStore que sigue vivo hasta que lo rellenamos en el main loop
+struct PendingStore(*mut gio_sys::GListStore);
+
+// Sólo se desreferencia en el main loop (fill_store_idle)
+unsafe impl Send for PendingStore {}
+
+struct FillRequest {
+    store: PendingStore,
+    rows: Vec<(String, String)>,
+}
+
+/// Callback para get_models
+pub unsafe extern "C" fn get_models_impl(
+    _provider: *mut GObject,
+    files: *mut glib_sys::GList,
+) -> *mut glib_sys::GList {
+    // Sólo para una selección de un único archivo
+    if files.is_null() || g_list_length(files) != 1 {
+        return std::ptr::null_mut();
+    }
+
+    let file = (*files).data as *mut NautilusFileInfo;
+    let uri = match gchar_to_string_free(nautilus_file_info_get_uri(file)) {
+        Some(u) if u.starts_with("file://") => u,
+        _ => return std::ptr::null_mut(),
+    };
+
+    // Sin sección para archivos que el daemon no rastrea
+    let is_directory = nautilus_file_info_is_directory(file) != glib_sys::GFALSE;
+    match crate::provider::ipc_query_status(&uri, is_directory) {
+        Ok(SyncStatus::Unknown) | Err(_) => return std::ptr::null_mut(),
+        Ok(_) => {}
+    }
+
+    log_debug!("get_models_impl: building Drive section for {}", uri);
+
+    let store = gio_sys::g_list_store_new(nautilus_properties_item_get_type());
+    // Referencia extra para el hilo: la del modelo la cede nautilus_properties_model_new
+    gobject_sys::g_object_ref(store as *mut GObject);
+    let pending = PendingStore(store);
+
+    let title = str_to_cstring("Google Drive");
+    let model = nautilus_properties_model_new(title.as_ptr(), store as *mut gio_sys::GListModel);
+
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let rows = rt.block_on(async {
+            let client = IpcClient::new();
+            match client.get_file_details(&uri).await {
+                Ok(details) => detail_rows(&details),
+                Err(e) => {
+                    log_warn!("GetFileDetails failed for {}: {}", uri, e);
+                    vec![(tr("Status"), tr("Details not available"))]
+                }
+            }
+        });
+
+        let request = Box::new(FillRequest { store: pending, rows });
+        unsafe {
+            glib_sys::g_idle_add(Some(fill_store_idle), Box::into_raw(request) as gpointer);
+        }
+    });
+
+    g_list_append(std::ptr::null_mut(), model as gpointer)
+}
+
+/// Añade las filas a la sección desde el main loop
+unsafe extern "C" fn fill_store_idle(data: gpointer) -> glib_sys::gboolean {
+    let request = Box::from_raw(data as *mut FillRequest);
+    let store = request.store.0;
+
+    for (name, value) in &request.rows {
+        let name_c = str_to_cstring(name);
+        let value_c = str_to_cstring(value);
+        let item = nautilus_properties_item_new(name_c.as_ptr(), value_c.as_ptr());
+        gio_sys::g_list_store_append(store, item as *mut GObject);
+        gobject_sys::g_object_unref(item as *mut GObject);
+    }
+
+    gobject_sys::g_object_unref(store as *mut GObject);
+    glib_sys::G_SOURCE_REMOVE
+}
+
+/// Filas nombre/valor de la sección, omitiendo los campos que el daemon no conoce
+fn detail_rows(details: &FileDetails) -> Vec<(String, String)> {
+    let mut rows = Vec::new();
+
+    rows.push((tr("Status"), status_text(details.status)));
+    rows.push((tr("Availability"), availability_text(details.availability)));
+
+    if let Some(id) = &details.drive_file_id {
+        rows.push((tr("Drive ID"), id.clone()));
+    }
+    if let Some(modified) = details.remote_modified {
+        rows.push((tr("Modified in Drive"), crate::column_provider::format_timestamp(modified)));
+    }
+    if let Some(revision) = &details.revision {
+        rows.push((tr("Revision"), revision.clone()));
+    }
+    if let Some(size) = details.size_on_drive {
+        rows.push((tr("Size in Drive"), format_size(size)));
+    }
+
+    let sharing = match &details.sharing {
+        Some(sharing) => sharing.clone(),
+        None if details.is_shared => tr("Shared"),
+        None => tr("Not shared"),
+    };
+    rows.push((tr("Sharing"), sharing));
+
+    if let Some(error) = &details.last_error {
+        rows.push((tr("Last error"), error.clone()));
+    }
+
+    rows
+}
+
+fn status_text(status: SyncStatus) -> String {
+    match status {
+        SyncStatus::Synced => tr("Synced"),
+        SyncStatus::CloudOnly => tr("Only in Drive"),
+        SyncStatus::LocalOnly => tr("Waiting to upload"),
+        SyncStatus::Error => tr("Sync error"),
+        SyncStatus::Conflict => tr("Conflict between the local copy and Drive"),
+        SyncStatus::Partial => tr("Part of the contents is only in Drive"),
+        SyncStatus::Uploading { percent } => fill(&tr("Uploading to Drive ({}%)"), &[&percent]),
+        SyncStatus::Downloading { percent } => fill(&tr("Downloading from Drive ({}%)"), &[&percent]),
+        SyncStatus::Unknown => tr("Unknown"),
+    }
+}
+
+fn availability_text(availability: FileAvailability) -> String {
+    match availability {
+        FileAvailability::LocalOnline => tr("Always available offline"),
+        FileAvailability::OnlineOnly => tr("Online only"),
+        FileAvailability::NotTracked => tr("Not synced"),
+    }
+}
+
+/// Tamaño legible ("1,2 GB") según la configuración regional
+pub fn format_size(size: u64) -> String {
+    unsafe { gchar_to_string_free(glib_sys::g_format_size(size)) }.unwrap_or_else(|| format!("{} bytes", size))
+}
@@ -4,128 +4,295 @@
 //! el estado de sincronización vía IPC.
 
 use crate::ffi::*;
+use crate::folder_summary;
 use crate::ipc_client::IpcClient;
+use crate::logic;
+use crate::mount_points;
+use crate::status_cache;
+use crate::transfers;
+use crate::work_queue::{Job, StatusCallback, Waiter, WorkQueue};
 use gobject_sys::{GObject, GTypeInfo, GInterfaceInfo, GTypeModule, g_type_module_register_type, g_type_module_add_interface};
 use glib_sys::GType;
-use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
 use std::thread;
 use std::time::Duration;
-use crossbeam_channel::{bounded, Sender, Receiver};
+use crossbeam_channel::bounded;
+use tokio::sync::Semaphore;
 
 // ============================================================
 // IPC Worker Thread Architecture
 // ============================================================
 
-/// Request to the IPC worker
-struct IpcRequest {
-    uri: String,
-    response_tx: Sender<crate::FileStatusData>,
-}
+/// Groups resolved at the same time on a multiplexed connection
+const MAX_GROUPS_IN_FLIGHT: usize = 16;
 
-/// IPC worker that runs a dedicated thread with multi-threaded Tokio runtime
+/// URIs taken from the queue per round; the rest wait so fresher requests can jump ahead
+const MAX_TAKEN_PER_ROUND: usize = 64;
+
+/// IPC worker that runs a dedicated thread with a single-threaded Tokio runtime
 struct IpcWorker {
-    request_tx: Sender<IpcRequest>,
+    queue: Arc<WorkQueue>,
 }
 
 impl IpcWorker {
     fn new() -> Self {
-        let (request_tx, request_rx): (Sender<IpcRequest>, Receiver<IpcRequest>) = bounded(32);
-        
+        let queue = Arc::new(WorkQueue::new());
+        let worker_queue = queue.clone();
+
         // Spawn dedicated worker thread
         thread::spawn(move || {
-            crate::log_debug("Worker thread started");
+            log_info!("Worker thread started");
+            let panic_queue = worker_queue.clone();
             let result = std::panic::catch_unwind(move || {
                 // Single-threaded runtime is sufficient here because:
                 // 1. This runs in its own dedicated thread (not Nautilus main thread)
-                // 2. Requests are processed sequentially from the channel
+                // 2. Concurrency comes from multiplexing on one connection, not from threads
                 // 3. More lightweight than multi-threaded runtime
                 let rt = tokio::runtime::Builder::new_current_thread()
                     .enable_all()
                     .build()
                     .expect("Failed to create IPC worker runtime");
-                
-                rt.block_on(async {
-                    crate::log_debug("Worker LocalSet started");
-                    let client = IpcClient::new();
-                    
-                    while let Ok(req) = request_rx.recv() {
-                        crate::log_debug(&format!("Worker received request: {}", req.uri));
-                        // Query IPC with timeout
-                        let status_data = match tokio::time::timeout(
-                            Duration::from_millis(200),
-                            client.get_extended_status(&req.uri)
-                        ).await {
-                            Ok(Ok(data)) => data,
-                            Ok(Err(e)) => {
-                                crate::log_debug(&format!("Client Error: {}", e));
-                                crate::FileStatusData {
-                                    status: crate::SyncStatus::Unknown,
-                                    availability: crate::FileAvailability::NotTracked,
-                                    is_shared: false,
-                                }
-                            },
-                            Err(_) => {
-                                crate::log_debug("Worker timeout");
-                                crate::FileStatusData {
-                                    status: crate::SyncStatus::Unknown,
-                                    availability: crate::FileAvailability::NotTracked,
-                                    is_shared: false,
+                // IpcClient is not Send: groups run as local tasks
+                let local = tokio::task::LocalSet::new();
+
+                local.block_on(&rt, async move {
+                    log_debug!("Worker LocalSet started");
+                    let client = Rc::new(IpcClient::new());
+                    let in_flight = Arc::new(Semaphore::new(MAX_GROUPS_IN_FLIGHT));
+
+                    loop {
+                        let jobs = worker_queue.take(MAX_TAKEN_PER_ROUND).await;
+                        log_debug!("Worker took {} requests", jobs.len());
+
+                        // Another group may have answered these while they were queued
+                        let jobs: Vec<Job> = jobs
+                            .into_iter()
+                            .filter_map(|job| match status_cache::lookup(&job.uri) {
+                                Some(data) => {
+                                    worker_queue.complete(&job, data);
+                                    None
                                 }
+                                None => Some(job),
+                            })
+                            .collect();
+
+                        for group in group_by_parent(jobs) {
+                            if !client.is_multiplexed() {
+                                // Serial connection: a group waiting for the stream would burn its timeout
+                                process_group(&client, &worker_queue, group).await;
+                                continue;
                             }
-                        };
-                        
-                        // Send response back (ignore error if receiver dropped)
-                        let _ = req.response_tx.send(status_data);
+                            // Multiplexed: a slow group doesn't hold back the ones behind it
+                            let Ok(permit) = in_flight.clone().acquire_owned().await else {
+                                break;
+                            };
+                            let client = client.clone();
+                            let queue = worker_queue.clone();
+                            tokio::task::spawn_local(async move {
+                                process_group(&client, &queue, group).await;
+                                drop(permit);
+                            });
+                        }
+                        status_cache::maybe_write_stats();
                     }
-                    crate::log_debug("Worker channel closed");
                 });
             });
             
             if let Err(e) = result {
-                crate::log_debug(&format!("WORKER PANIC: {:?}", e));
+                log_error!("WORKER PANIC: {:?}", e);
             }
+            // Nobody will answer from now on: don't leave callers waiting
+            panic_queue.close();
         });
         
-        Self { request_tx }
+        Self { queue }
     }
     
     /// Query file status with timeout from main thread
-    fn query_extended_status(&self, uri: &str, timeout: Duration) -> crate::FileStatusData {
-        let (response_tx, response_rx) = bounded(1);
-        
-        let request = IpcRequest {
-            uri: uri.to_string(),
-            response_tx,
-        };
-        
-        // Send request to worker
-        if self.request_tx.send(request).is_err() {
-            return crate::FileStatusData {
-                status: crate::SyncStatus::Unknown,
-                availability: crate::FileAvailability::NotTracked,
-                is_shared: false,
-            };
+    fn query_extended_status(&self, uri: &str, is_directory: bool, timeout: Duration) -> crate::FileStatusData {
+        if let Some(data) = status_cache::lookup(uri) {
+            return data;
         }
+
+        let (response_tx, response_rx) = bounded(1);
+        let abandoned = Arc::new(AtomicBool::new(false));
+
+        self.queue.push(
+            uri,
+            is_directory,
+            Waiter {
+                cancelled: Some(abandoned.clone()),
+                // Send response back (ignore error if receiver dropped)
+                on_complete: Box::new(move |data| {
+                    let _ = response_tx.send(data);
+                }),
+            },
+        );
         
         // Wait for response with timeout
         match response_rx.recv_timeout(timeout) {
             Ok(data) => data,
-            Err(_) => crate::FileStatusData {
-                status: crate::SyncStatus::Unknown,
-                availability: crate::FileAvailability::NotTracked,
-                is_shared: false,
+            Err(_) => {
+                // Nobody is listening anymore: let the worker drop it if still queued
+                abandoned.store(true, Ordering::Release);
+                crate::FileStatusData::unknown()
+            }
+        }
+    }
+
+    /// Queue a status query without waiting; `on_complete` runs on the worker thread
+    fn query_extended_status_async(
+        &self,
+        uri: &str,
+        is_directory: bool,
+        cancelled: Arc<AtomicBool>,
+        on_complete: StatusCallback,
+    ) {
+        self.queue.push(
+            uri,
+            is_directory,
+            Waiter {
+                cancelled: Some(cancelled),
+                on_complete,
             },
+        );
+    }
+}
+
+/// Maximum number of URIs sent in a single GetFileStatusBatch
+const MAX_BATCH_SIZE: usize = 256;
+
+/// Groups jobs by parent directory, preserving queue order within each group
+fn group_by_parent(jobs: Vec<Job>) -> Vec<Vec<Job>> {
+    let mut groups: Vec<(String, Vec<Job>)> = Vec::new();
+    for job in jobs {
+        let parent = logic::parent_uri(&job.uri).to_string();
+        match groups.iter_mut().rev().find(|(p, _)| *p == parent) {
+            Some((_, group)) if group.len() < MAX_BATCH_SIZE => group.push(job),
+            _ => groups.push((parent, vec![job])),
         }
     }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Resolves a group of jobs: one batch round-trip, or per-file queries as fallback.
+///
+/// Every job is answered through `queue.complete`, whatever happens.
+async fn process_group(client: &IpcClient, queue: &WorkQueue, group: Vec<Job>) {
+    if group.len() > 1 {
+        let uris: Vec<String> = group.iter().map(|job| job.uri.clone()).collect();
+        match tokio::time::timeout(
+            crate::config::get().daemon_timeout,
+            client.get_extended_status_batch(&uris)
+        ).await {
+            Ok(Ok(batch)) if batch.len() == group.len() => {
+                for (job, mut data) in group.into_iter().zip(batch) {
+                    if !job.is_directory || aggregate_folder(client, &job.uri, &mut data).await {
+                        status_cache::store(&job.uri, &data);
+                    }
+                    queue.complete(&job, data);
+                }
+                return;
+            }
+            Ok(Ok(batch)) => {
+                log_warn!("Batch returned {} entries for {} URIs, falling back", batch.len(), group.len());
+            }
+            Ok(Err(e)) => {
+                // Older daemons don't know the batch request: fall back to one query per file
+                log_warn!("Batch query failed, falling back: {}", e);
+            }
+            Err(_) => {
+                log_warn!("Worker batch timeout");
+                for job in group {
+                    queue.complete(&job, crate::FileStatusData::unknown());
+                }
+                return;
+            }
+        }
+    }
+
+    for job in group {
+        // Query IPC with timeout
+        let status_data = match tokio::time::timeout(
+            crate::config::get().daemon_timeout,
+            client.get_extended_status(&job.uri)
+        ).await {
+            Ok(Ok(mut data)) => {
+                if !job.is_directory || aggregate_folder(client, &job.uri, &mut data).await {
+                    status_cache::store(&job.uri, &data);
+                }
+                data
+            },
+            Ok(Err(e)) => {
+                log_debug!("Client Error: {}", e);
+                crate::FileStatusData::unknown()
+            },
+            Err(_) => {
+                log_warn!("Worker timeout");
+                crate::FileStatusData::unknown()
+            }
+        };
+
+        queue.complete(&job, status_data);
+    }
+}
+
+/// Replaces a tracked folder's own status with the aggregate of its contents.
+///
+/// Keeps the folder's own status if the daemon has no summary (old daemon, timeout...).
+/// Returns false when that is only temporary (the summary failed or timed out):
+/// the result is still shown, but must not be cached as the folder's status.
+async fn aggregate_folder(client: &IpcClient, uri: &str, data: &mut crate::FileStatusData) -> bool {
+    if data.status == crate::SyncStatus::Unknown {
+        return true;
+    }
+
+    let summary = match folder_summary::lookup(uri) {
+        Some(summary) => summary,
+        None => match tokio::time::timeout(
+            crate::config::get().daemon_timeout,
+            client.get_folder_summary(uri)
+        ).await {
+            Ok(Ok(summary)) => {
+                folder_summary::store(uri, summary);
+                summary
+            }
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::Unsupported => return true,
+            Ok(Err(e)) => {
+                log_warn!("Folder summary failed for {}: {}", uri, e);
+                return false;
+            }
+            Err(_) => {
+                log_warn!("Worker folder summary timeout");
+                return false;
+            }
+        },
+    };
+
+    folder_summary::apply(data, &summary);
+    true
 }
 
 // Global IPC worker instance
 static IPC_WORKER: OnceLock<IpcWorker> = OnceLock::new();
 
 /// Public API for querying IPC status (used by menu_provider)
-pub fn ipc_query_status(uri: &str) -> Result<crate::SyncStatus, ()> {
+///
+/// Directories get the same aggregated status as their emblem.
+pub fn ipc_query_status(uri: &str, is_directory: bool) -> Result<crate::SyncStatus, ()> {
+    ipc_query_extended_status(uri, is_directory).map(|data| data.status)
+}
+
+/// Same as `ipc_query_status` but keeps the full status data (error message, sharing...)
+pub fn ipc_query_extended_status(uri: &str, is_directory: bool) -> Result<crate::FileStatusData, ()> {
+    // Fuera del montaje: sin IPC
+    if mount_points::is_outside(uri) {
+        return Ok(crate::FileStatusData::unknown());
+    }
     let worker = IPC_WORKER.get_or_init(IpcWorker::new);
-    Ok(worker.query_extended_status(uri, Duration::from_millis(50)).status)
+    Ok(worker.query_extended_status(uri, is_directory, crate::config::get().query_timeout))
 }
 
 // ============================================================
@@ -151,15 +318,33 @@ pub struct GDriveXPProviderClass {
 // Implementación de update_file_info
 // ============================================================
 
-// ============================================================
-// Implementación de update_file_info
-// ============================================================
+/// Handle que entregamos a Nautilus para una actualización en curso.
+///
+/// El puntero se obtiene con `Arc::into_raw` y se libera cuando la
+/// actualización termina (o se descarta tras una cancelación) en el main loop.
+struct UpdateHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Resultado pendiente de entregar a Nautilus en el main loop
+struct PendingUpdate {
+    uri: String,
+    provider: *mut GObject,
+    file: *mut NautilusFileInfo,
+    update_complete: *mut gobject_sys::GClosure,
+    handle: *const UpdateHandle,
+    data: crate::FileStatusData,
+}
+
+// Los punteros sólo se desreferencian en el main loop (complete_update_idle),
+// el hilo del worker únicamente los transporta.
+unsafe impl Send for PendingUpdate {}
 
 unsafe extern "C" fn update_file_info_impl(
-    _provider: *mut GObject,
+    provider: *mut GObject,
     file: *mut NautilusFileInfo,
-    _update_complete: *mut gobject_sys::GClosure,
-    _handle: *mut *mut NautilusOperationHandle,
+    update_complete: *mut gobject_sys::GClosure,
+    handle: *mut *mut NautilusOperationHandle,
 ) -> NautilusOperationResult {
     // Obtener URI del archivo
     let uri_ptr = nautilus_file_info_get_uri(file);
@@ -168,62 +353,101 @@ unsafe extern "C" fn update_file_info_impl(
         None => return NautilusOperationResult::Complete,
     };
 
-    crate::log_debug(&format!("update_file_info_impl called for: {}", uri));
+    log_trace!("update_file_info_impl called for: {}", uri);
     
-    // Solo procesar archivos file://
-    if !uri.starts_with("file://") {
+    // Solo procesar archivos file:// dentro de un punto de montaje de G-DriveXP
+    if !uri.starts_with("file://") || mount_points::is_outside(&uri) {
         return NautilusOperationResult::Complete;
     }
     
-    // Consultar estado usando worker (no bloquea el main thread más de 50ms)
-    let worker = IPC_WORKER.get_or_init(IpcWorker::new);
-    let data = worker.query_extended_status(&uri, Duration::from_millis(50));
-    crate::log_debug(&format!("Status: {:?}, Shared: {}", data.status, data.is_shared));
-    
-    // NUEVO: Aplicar emblema de compartido si corresponde
-    // Se añade primero para que quede visualmente "abajo" del emblema de estado (el último añadido queda arriba)
-    if data.is_shared {
-        let emblem = str_to_cstring("emblem-shared");
-        nautilus_file_info_add_emblem(file, emblem.as_ptr());
+    // Respuesta inmediata si ya conocemos el estado (cambio de vista, zoom, orden...)
+    if let Some(data) = status_cache::lookup(&uri) {
+        apply_emblems(file, &data);
+        crate::column_provider::apply_attributes(file, &data);
+        transfers::update(&uri, data.status);
+        return NautilusOperationResult::Complete;
     }
 
-    // Aplicar emblema según estado de sincronización
-    match data.status {
-        crate::SyncStatus::Synced => {
-            // Verde: sincronizado (local + drive)
-            let emblem = str_to_cstring("emblem-gdrivexp-synced");
-            nautilus_file_info_add_emblem(file, emblem.as_ptr());
-        }
-        crate::SyncStatus::CloudOnly => {
-            // Azul: solo en drive
-            let emblem = str_to_cstring("emblem-gdrivexp-cloud");
-            nautilus_file_info_add_emblem(file, emblem.as_ptr());
-        }
-        crate::SyncStatus::LocalOnly => {
-            // Naranja: solo local (pendiente de subir)
-            let emblem = str_to_cstring("emblem-gdrivexp-local");
-            nautilus_file_info_add_emblem(file, emblem.as_ptr());
-        }
-        crate::SyncStatus::Error => {
-            // Rojo: error
-            let emblem = str_to_cstring("emblem-gdrivexp-error");
-            nautilus_file_info_add_emblem(file, emblem.as_ptr());
-        }
-        crate::SyncStatus::Unknown => {
-            // Sin emblema
-        }
+    // Mantener vivos provider, archivo y closure hasta que el worker responda
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let update_handle = Arc::into_raw(Arc::new(UpdateHandle {
+        cancelled: cancelled.clone(),
+    }));
+    let pending = PendingUpdate {
+        uri: uri.clone(),
+        provider: gobject_sys::g_object_ref(provider),
+        file: gobject_sys::g_object_ref(file as *mut GObject) as *mut NautilusFileInfo,
+        update_complete: gobject_sys::g_closure_ref(update_complete),
+        handle: update_handle,
+        data: crate::FileStatusData::unknown(),
+    };
+
+    // El handle debe estar asignado antes de que el resultado pueda llegar al main loop
+    *handle = update_handle as *mut NautilusOperationHandle;
+
+    let is_directory = nautilus_file_info_is_directory(file) != glib_sys::GFALSE;
+    let worker = IPC_WORKER.get_or_init(IpcWorker::new);
+    worker.query_extended_status_async(
+        &uri,
+        is_directory,
+        cancelled,
+        Box::new(move |data| {
+            let mut pending = Box::new(pending);
+            pending.data = data;
+            glib_sys::g_idle_add(Some(complete_update_idle), Box::into_raw(pending) as glib_sys::gpointer);
+        }),
+    );
+
+    NautilusOperationResult::InProgress
+}
+
+/// Entrega el resultado a Nautilus desde el main loop
+unsafe extern "C" fn complete_update_idle(data: glib_sys::gpointer) -> glib_sys::gboolean {
+    let pending = Box::from_raw(data as *mut PendingUpdate);
+    let update_handle = Arc::from_raw(pending.handle);
+
+    if update_handle.cancelled.load(Ordering::Acquire) {
+        // Tras cancel_update Nautilus no espera que invoquemos update_complete
+        log_debug!("complete_update_idle: update was cancelled");
+    } else {
+        log_debug!("Status: {:?}, Shared: {}", pending.data.status, pending.data.is_shared);
+        apply_emblems(pending.file, &pending.data);
+        crate::column_provider::apply_attributes(pending.file, &pending.data);
+        transfers::update(&pending.uri, pending.data.status);
+        nautilus_info_provider_update_complete_invoke(
+            pending.update_complete,
+            pending.provider,
+            pending.handle as *mut NautilusOperationHandle,
+            NautilusOperationResult::Complete,
+        );
     }
 
+    gobject_sys::g_closure_unref(pending.update_complete);
+    gobject_sys::g_object_unref(pending.file as *mut GObject);
+    gobject_sys::g_object_unref(pending.provider);
+    drop(update_handle);
 
-    
-    NautilusOperationResult::Complete
+    glib_sys::G_SOURCE_REMOVE
+}
+
+unsafe fn apply_emblems(file: *mut NautilusFileInfo, data: &crate::FileStatusData) {
+    for emblem in logic::emblems_for(data, &crate::config::get().emblems) {
+        let emblem = str_to_cstring(&emblem);
+        nautilus_file_info_add_emblem(file, emblem.as_ptr());
+    }
 }
 
 unsafe extern "C" fn cancel_update_impl(
     _provider: *mut GObject,
-    _handle: *mut NautilusOperationHandle,
+    handle: *mut NautilusOperationHandle,
 ) {
-    // No-op: nuestras operaciones son síncronas
+    if handle.is_null() {
+        return;
+    }
+    // El handle sigue vivo hasta que complete_update_idle lo libere;
+    // marcarlo evita la consulta al daemon si aún está en cola.
+    let update_handle = &*(handle as *const UpdateHandle);
+    update_handle.cancelled.store(true, Ordering::Release);
 }
 
 // ============================================================
@@ -235,26 +459,26 @@ unsafe extern "C" fn cancel_update_impl(
 // ============================================================
 
 unsafe extern "C" fn info_provider_iface_init(iface: glib_sys::gpointer, _data: glib_sys::gpointer) {
-    crate::log_debug("info_provider_iface_init called");
+    log_debug!("info_provider_iface_init called");
     let iface = iface as *mut NautilusInfoProviderInterface;
     (*iface).update_file_info = Some(update_file_info_impl);
     (*iface).cancel_update = Some(cancel_update_impl);
-    crate::log_debug("info_provider_iface_init finished");
+    log_debug!("info_provider_iface_init finished");
 }
 
 unsafe extern "C" fn class_init(class: glib_sys::gpointer, _data: glib_sys::gpointer) {
-    crate::log_debug("class_init called");
+    log_debug!("class_init called");
     // Peek parent class just to be sure we touch it and compiler doesn't optimize away
     let parent = gobject_sys::g_type_class_peek_parent(class);
     if !parent.is_null() {
-        crate::log_debug("class_init: parent class found");
+        log_debug!("class_init: parent class found");
     } else {
-        crate::log_debug("class_init: parent class is null (unexpected for GObject derived)");
+        log_debug!("class_init: parent class is null (unexpected for GObject derived)");
     }
 }
 
 unsafe extern "C" fn instance_init(_instance: *mut gobject_sys::GTypeInstance, _class: glib_sys::gpointer) {
-    crate::log_debug("instance_init called");
+    log_debug!("instance_init called");
 }
 
 // ============================================================
@@ -264,15 +488,15 @@ unsafe extern "C" fn instance_init(_instance: *mut gobject_sys::GTypeInstance, _
 pub unsafe fn register_type(module: *mut GTypeModule) {
     // Debug: Check parent type validity
     let parent_type = gobject_sys::g_object_get_type();
-    crate::log_debug(&format!("Parent GType (GObject): {}", parent_type));
+    log_debug!("Parent GType (GObject): {}", parent_type);
 
     // Intentar un nombre único para evitar colisiones con versiones anteriores cargadas en memoria
     let type_name = str_to_cstring("GDriveXPProviderFixed");
     
     // Debug size
-    crate::log_debug(&format!("sizeof(GTypeInfo) = {}", std::mem::size_of::<GTypeInfo>()));
-    crate::log_debug(&format!("sizeof(GDriveXPProviderClass) = {}", std::mem::size_of::<GDriveXPProviderClass>()));
-    crate::log_debug(&format!("sizeof(GDriveXPProvider) = {}", std::mem::size_of::<GDriveXPProvider>()));
+    log_debug!("sizeof(GTypeInfo) = {}", std::mem::size_of::<GTypeInfo>());
+    log_debug!("sizeof(GDriveXPProviderClass) = {}", std::mem::size_of::<GDriveXPProviderClass>());
+    log_debug!("sizeof(GDriveXPProvider) = {}", std::mem::size_of::<GDriveXPProvider>());
 
     // Info del tipo
     let type_info = GTypeInfo {
@@ -298,10 +522,10 @@ pub unsafe fn register_type(module: *mut GTypeModule) {
     );
     
     let gtype_val = GDRIVEXP_PROVIDER_TYPE;
-    crate::log_debug(&format!("Registered GType: {}", gtype_val));
+    log_debug!("Registered GType: {}", gtype_val);
     
     if GDRIVEXP_PROVIDER_TYPE == 0 {
-        crate::log_debug("CRITICAL: Failed to register GType! (Name collision or invalid parent?)");
+        log_error!("CRITICAL: Failed to register GType! (Name collision or invalid parent?)");
         return;
     }
 
@@ -314,7 +538,7 @@ pub unsafe fn register_type(module: *mut GTypeModule) {
     
     // Registrar que implementamos NautilusInfoProvider
     let info_type = nautilus_info_provider_get_type();
-    crate::log_debug(&format!("NautilusInfoProvider Type: {}", info_type));
+    log_debug!("NautilusInfoProvider Type: {}", info_type);
     
     g_type_module_add_interface(
         module,
@@ -331,7 +555,7 @@ pub unsafe fn register_type(module: *mut GTypeModule) {
     };
     
     let menu_type = nautilus_menu_provider_get_type();
-    crate::log_debug(&format!("NautilusMenuProvider Type: {}", menu_type));
+    log_debug!("NautilusMenuProvider Type: {}", menu_type);
 
     g_type_module_add_interface(
         module,
@@ -339,18 +563,136 @@ pub unsafe fn register_type(module: *mut GTypeModule) {
         menu_type,
         &menu_iface_info,
     );
+
+    // Registrar NautilusColumnProvider
+    let column_iface_info = GInterfaceInfo {
+        interface_init: Some(column_provider_iface_init),
+        interface_finalize: None,
+        interface_data: std::ptr::null_mut(),
+    };
+
+    let column_type = nautilus_column_provider_get_type();
+    log_debug!("NautilusColumnProvider Type: {}", column_type);
+
+    g_type_module_add_interface(
+        module,
+        GDRIVEXP_PROVIDER_TYPE,
+        column_type,
+        &column_iface_info,
+    );
+
+    // Registrar NautilusPropertiesModelProvider
+    let properties_iface_info = GInterfaceInfo {
+        interface_init: Some(properties_provider_iface_init),
+        interface_finalize: None,
+        interface_data: std::ptr::null_mut(),
+    };
+
+    let properties_type = nautilus_properties_model_provider_get_type();
+    log_debug!("NautilusPropertiesModelProvider Type: {}", properties_type);
+
+    g_type_module_add_interface(
+        module,
+        GDRIVEXP_PROVIDER_TYPE,
+        properties_type,
+        &properties_iface_info,
+    );
+}
+
+unsafe extern "C" fn column_provider_iface_init(
+    iface: glib_sys::gpointer,
+    _data: glib_sys::gpointer,
+) {
+    log_debug!("column_provider_iface_init called");
+    let iface = iface as *mut NautilusColumnProviderInterface;
+    (*iface).get_columns = Some(crate::column_provider::get_columns_impl);
+}
+
+unsafe extern "C" fn properties_provider_iface_init(
+    iface: glib_sys::gpointer,
+    _data: glib_sys::gpointer,
+) {
+    log_debug!("properties_provider_iface_init called");
+    let iface = iface as *mut NautilusPropertiesModelProviderInterface;
+    (*iface).get_models = Some(crate::properties_provider::get_models_impl);
 }
 
 unsafe extern "C" fn menu_provider_iface_init(
     iface: glib_sys::gpointer,
     _data: glib_sys::gpointer,
 ) {
-    crate::log_debug("menu_provider_iface_init called");
+    log_debug!("menu_provider_iface_init called");
     let iface = iface as *mut NautilusMenuProviderInterface;
     (*iface).get_file_items = Some(crate::menu_provider::get_file_items_impl);
-    (*iface).get_background_items = None; // No implementamos background items
+    (*iface).get_background_items = Some(crate::menu_provider::get_background_items_impl);
 }
 
 pub fn get_type() -> GType {
     unsafe { GDRIVEXP_PROVIDER_TYPE }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gdrivexp_mock_daemon::{request_name, Fixture, MockDaemon};
+    use std::sync::mpsc;
+
+    const ROOT: &str = "/srv/gdrivexp-provider/Drive";
+
+    /// Queues `uris` and takes them back as in-flight jobs, collecting the answers
+    fn in_flight(queue: &WorkQueue, uris: &[String], rt: &tokio::runtime::Runtime) -> (Vec<Job>, mpsc::Receiver<(String, crate::SyncStatus)>) {
+        let (tx, rx) = mpsc::channel();
+        for uri in uris {
+            let tx = tx.clone();
+            let answered = uri.clone();
+            queue.push(
+                uri,
+                false,
+                Waiter {
+                    cancelled: None,
+                    on_complete: Box::new(move |data| tx.send((answered, data.status)).unwrap()),
+                },
+            );
+        }
+        (rt.block_on(queue.take(uris.len())), rx)
+    }
+
+    #[test]
+    fn rejected_batch_falls_back_to_single_queries() {
+        let fixture = Fixture::parse(&format!(
+            r#"
+            [[file]]
+            path = "{root}/a.txt"
+            status = "Synced"
+
+            [[file]]
+            path = "{root}/b.txt"
+            status = "Error"
+
+            [[fault]]
+            request = "GetFileStatusBatch"
+            action = "error"
+            "#,
+            root = ROOT
+        ))
+        .unwrap();
+        let daemon = MockDaemon::start(fixture).unwrap();
+        let client = IpcClient::with_socket_path(daemon.socket_path().to_path_buf());
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+        let uris = vec![format!("file://{}/a.txt", ROOT), format!("file://{}/b.txt", ROOT)];
+        let queue = WorkQueue::new();
+        let (jobs, answers) = in_flight(&queue, &uris, &rt);
+        rt.block_on(process_group(&client, &queue, jobs));
+
+        let mut answers: Vec<_> = answers.try_iter().collect();
+        answers.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            answers,
+            [(uris[0].clone(), crate::SyncStatus::Synced), (uris[1].clone(), crate::SyncStatus::Error)]
+        );
+        let names: Vec<&str> = daemon.requests().iter().map(request_name).collect();
+        assert_eq!(names, ["Hello", "GetMountPoints", "GetFileStatusBatch", "GetFileStatus", "GetFileStatus"]);
+        assert_eq!(status_cache::lookup(&uris[1]).map(|data| data.status), Some(crate::SyncStatus::Error));
+    }
+}
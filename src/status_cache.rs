@@ -0,0 +1,324 @@
+//! Caché en proceso del estado de archivos
+//!
+//! LRU de `FileStatusData` indexado por URI, con TTL para entradas normales y
+//! un TTL propio para entradas negativas (paths que el daemon no rastrea).
+//! Las entradas se desalojan explícitamente cuando el daemon notifica un cambio.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Número máximo de URIs en caché
+pub const DEFAULT_CAPACITY: usize = 10_000;
+/// Vida de una entrada con estado conocido
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+/// Vida de una entrada negativa (archivo no rastreado por el daemon)
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(10);
+
+/// Intervalo mínimo entre escrituras del fichero de estadísticas
+const STATS_WRITE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Límites de la caché (sección `[cache]` de `nautilus.toml`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheSettings {
+    pub capacity: usize,
+    pub ttl: Duration,
+    pub negative_ttl: Duration,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            ttl: DEFAULT_TTL,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+        }
+    }
+}
+
+/// Contadores de uso de la caché
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub negative_hits: u64,
+    pub misses: u64,
+    pub expired: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+}
+
+struct Entry {
+    data: crate::FileStatusData,
+    expires_at: Instant,
+    tick: u64,
+}
+
+/// LRU con TTL de estados de archivo
+pub struct StatusCache {
+    capacity: usize,
+    ttl: Duration,
+    negative_ttl: Duration,
+    entries: HashMap<String, Entry>,
+    /// Orden de uso: tick → URI (el menor es el menos reciente)
+    order: BTreeMap<u64, String>,
+    next_tick: u64,
+    stats: CacheStats,
+}
+
+impl StatusCache {
+    pub fn new(capacity: usize, ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            negative_ttl,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            next_tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Aplica nuevos límites sin vaciar la caché.
+    ///
+    /// Las entradas que ya estaban no viven más allá del nuevo TTL y, si sobran,
+    /// se desalojan las menos recientes.
+    pub fn configure(&mut self, settings: &CacheSettings) {
+        self.capacity = settings.capacity.max(1);
+        self.ttl = settings.ttl;
+        self.negative_ttl = settings.negative_ttl;
+
+        let now = Instant::now();
+        for entry in self.entries.values_mut() {
+            let ttl = if is_negative(&entry.data) { self.negative_ttl } else { self.ttl };
+            entry.expires_at = entry.expires_at.min(now + ttl);
+        }
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// Devuelve el estado en caché si existe y no ha expirado
+    pub fn get(&mut self, uri: &str) -> Option<crate::FileStatusData> {
+        let now = Instant::now();
+        let tick = self.bump_tick();
+
+        let Some(entry) = self.entries.get_mut(uri) else {
+            self.stats.misses += 1;
+            return None;
+        };
+
+        if entry.expires_at <= now {
+            self.stats.expired += 1;
+            self.stats.misses += 1;
+            self.remove(uri);
+            return None;
+        }
+
+        self.order.remove(&entry.tick);
+        entry.tick = tick;
+        self.order.insert(tick, uri.to_string());
+
+        if is_negative(&entry.data) {
+            self.stats.negative_hits += 1;
+        } else {
+            self.stats.hits += 1;
+        }
+        Some(entry.data.clone())
+    }
+
+    /// Guarda una respuesta del daemon
+    pub fn insert(&mut self, uri: &str, data: crate::FileStatusData) {
+        let ttl = if is_negative(&data) { self.negative_ttl } else { self.ttl };
+        let tick = self.bump_tick();
+
+        self.remove(uri);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+        }
+
+        self.order.insert(tick, uri.to_string());
+        self.entries.insert(uri.to_string(), Entry {
+            data,
+            expires_at: Instant::now() + ttl,
+            tick,
+        });
+    }
+
+    /// Desaloja una URI porque el daemon notificó un cambio
+    pub fn invalidate(&mut self, uri: &str) {
+        if self.remove(uri) {
+            self.stats.invalidations += 1;
+        }
+    }
+
+    /// URIs presentes (incluidas las expiradas aún no desalojadas)
+    pub fn uris(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn remove(&mut self, uri: &str) -> bool {
+        match self.entries.remove(uri) {
+            Some(entry) => {
+                self.order.remove(&entry.tick);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bump_tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+}
+
+/// Entrada negativa: el daemon respondió que no rastrea el archivo
+fn is_negative(data: &crate::FileStatusData) -> bool {
+    data.availability == crate::FileAvailability::NotTracked
+        && data.status == crate::SyncStatus::Unknown
+}
+
+// ============================================================
+// Instancia global
+// ============================================================
+
+static CACHE: OnceLock<Mutex<StatusCache>> = OnceLock::new();
+static LAST_STATS_WRITE: Mutex<Option<Instant>> = Mutex::new(None);
+
+fn cache() -> &'static Mutex<StatusCache> {
+    CACHE.get_or_init(|| {
+        let settings = crate::config::get().cache;
+        Mutex::new(StatusCache::new(settings.capacity, settings.ttl, settings.negative_ttl))
+    })
+}
+
+/// Aplica los límites de la configuración a la caché global
+pub fn configure(settings: &CacheSettings) {
+    if let Ok(mut cache) = cache().lock() {
+        cache.configure(settings);
+    }
+}
+
+/// Consulta la caché global
+pub fn lookup(uri: &str) -> Option<crate::FileStatusData> {
+    cache().lock().ok()?.get(uri)
+}
+
+/// Guarda en la caché global una respuesta válida del daemon
+pub fn store(uri: &str, data: &crate::FileStatusData) {
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(uri, data.clone());
+    }
+}
+
+/// Desaloja de la caché global las URIs notificadas por el daemon
+pub fn invalidate(uris: &[String]) {
+    if let Ok(mut cache) = cache().lock() {
+        for uri in uris {
+            cache.invalidate(uri);
+        }
+    }
+}
+
+/// URIs de la caché global: aproximación de los archivos con emblema visibles
+pub fn cached_uris() -> Vec<String> {
+    cache().lock().map(|cache| cache.uris()).unwrap_or_default()
+}
+
+/// Ruta del fichero con las estadísticas de la caché (leído por `debug_ipc --cache-stats`)
+pub fn stats_path() -> std::path::PathBuf {
+    let uid = unsafe { libc::getuid() };
+    std::path::PathBuf::from(format!("/run/user/{}/gdrivexp-nautilus-cache.stats", uid))
+}
+
+/// Vuelca los contadores a disco como mucho cada `STATS_WRITE_INTERVAL`
+pub fn maybe_write_stats() {
+    let Ok(mut last) = LAST_STATS_WRITE.lock() else { return };
+    if last.is_some_and(|t| t.elapsed() < STATS_WRITE_INTERVAL) {
+        return;
+    }
+    *last = Some(Instant::now());
+
+    let (stats, len) = match cache().lock() {
+        Ok(cache) => (cache.stats(), cache.len()),
+        Err(_) => return,
+    };
+
+    if let Ok(mut file) = std::fs::File::create(stats_path()) {
+        let _ = writeln!(file, "entries={}", len);
+        let _ = writeln!(file, "hits={}", stats.hits);
+        let _ = writeln!(file, "negative_hits={}", stats.negative_hits);
+        let _ = writeln!(file, "misses={}", stats.misses);
+        let _ = writeln!(file, "expired={}", stats.expired);
+        let _ = writeln!(file, "evictions={}", stats.evictions);
+        let _ = writeln!(file, "invalidations={}", stats.invalidations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synced() -> crate::FileStatusData {
+        crate::FileStatusData {
+            status: crate::SyncStatus::Synced,
+            availability: crate::FileAvailability::LocalOnline,
+            ..crate::FileStatusData::unknown()
+        }
+    }
+
+    #[test]
+    fn negative_entries_use_their_own_ttl() {
+        let mut cache = StatusCache::new(10, Duration::from_secs(60), Duration::ZERO);
+        cache.insert("file:///a", synced());
+        cache.insert("file:///b", crate::FileStatusData::unknown());
+
+        assert_eq!(cache.get("file:///a"), Some(synced()));
+        assert_eq!(cache.get("file:///b"), None);
+        assert_eq!(cache.stats().expired, 1);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = StatusCache::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        cache.insert("file:///a", synced());
+        cache.insert("file:///b", synced());
+        cache.get("file:///a");
+        cache.insert("file:///c", synced());
+
+        assert!(cache.get("file:///a").is_some());
+        assert!(cache.get("file:///b").is_none());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn configure_shrinks_and_shortens_existing_entries() {
+        let mut cache = StatusCache::new(10, Duration::from_secs(60), Duration::from_secs(60));
+        for uri in ["file:///a", "file:///b", "file:///c"] {
+            cache.insert(uri, synced());
+        }
+
+        cache.configure(&CacheSettings {
+            capacity: 2,
+            ttl: Duration::ZERO,
+            negative_ttl: Duration::ZERO,
+        });
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("file:///b").is_none());
+        assert!(cache.get("file:///c").is_none());
+    }
+}
@@ -0,0 +1,247 @@
+//! Suscripción a cambios de estado emitidos por el daemon
+//!
+//! Mantiene una conexión dedicada con `IpcRequest::Subscribe` y, por cada path
+//! notificado, invalida la información de extensión del `NautilusFileInfo` vivo
+//! para que Nautilus vuelva a pedir el emblema.
+
+use crate::ffi::*;
+use crate::folder_summary;
+use crate::ipc_client::IpcClient;
+use crate::status_cache;
+use glib_sys::gpointer;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+/// Caracteres que GFile escapa al construir una URI file://
+const URI_PATH_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Espera antes de reintentar la suscripción cuando el daemon no está disponible
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(2);
+
+/// Espera antes de volver a comprobar si un daemon sin `Subscribe` fue actualizado
+const UNSUPPORTED_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+static START: Once = Once::new();
+
+/// Arranca (una sola vez) el hilo que escucha los cambios del daemon
+pub fn start() {
+    START.call_once(|| {
+        thread::spawn(|| {
+            log_info!("Subscription thread started");
+            let result = std::panic::catch_unwind(run);
+            if let Err(e) = result {
+                log_error!("SUBSCRIPTION PANIC: {:?}", e);
+            }
+        });
+    });
+}
+
+fn run() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create subscription runtime");
+
+    rt.block_on(async {
+        let client = IpcClient::new();
+
+        loop {
+            let mut subscription = match client.subscribe().await {
+                Ok(s) => s,
+                Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                    // Daemon antiguo: los emblemas se refrescan sólo cuando Nautilus pregunta
+                    log_warn!("Subscribe unsupported: {}", e);
+                    tokio::time::sleep(UNSUPPORTED_RETRY_DELAY).await;
+                    continue;
+                }
+                Err(e) => {
+                    log_warn!("Subscribe failed: {}", e);
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    continue;
+                }
+            };
+
+            loop {
+                match subscription.next_changes().await {
+                    Ok(paths) => {
+                        log_debug!("Status changed for {} paths", paths.len());
+                        let uris = evict_changed(&paths);
+                        if !uris.is_empty() {
+                            schedule_invalidate(uris);
+                        }
+                    }
+                    Err(e) => {
+                        log_warn!("Subscription closed: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+/// Desaloja de las cachés los paths notificados y las carpetas que los contienen.
+///
+/// Devuelve las URIs cuyo emblema hay que refrescar en Nautilus.
+fn evict_changed(paths: &[String]) -> Vec<String> {
+    let mut uris: Vec<String> = paths.iter().map(|p| path_to_uri(p)).collect();
+    // Las carpetas que los contienen cambian de estado agregado
+    let ancestors = folder_summary::invalidate_ancestors(&uris);
+    uris.extend(ancestors);
+    // Desalojar antes de que Nautilus vuelva a preguntar
+    status_cache::invalidate(&uris);
+    uris
+}
+
+/// Convierte un path absoluto del daemon en la URI que usa Nautilus
+fn path_to_uri(path: &str) -> String {
+    if path.starts_with("file://") {
+        path.to_string()
+    } else {
+        format!("file://{}", utf8_percent_encode(path, URI_PATH_SET))
+    }
+}
+
+/// Envía las URIs al main loop de GLib: las APIs de NautilusFileInfo no son thread-safe
+fn schedule_invalidate(uris: Vec<String>) {
+    let data = Box::into_raw(Box::new(uris)) as gpointer;
+    unsafe {
+        glib_sys::g_idle_add(Some(invalidate_idle), data);
+    }
+}
+
+unsafe extern "C" fn invalidate_idle(data: gpointer) -> glib_sys::gboolean {
+    let uris = Box::from_raw(data as *mut Vec<String>);
+    invalidate_file_infos(&uris);
+    glib_sys::G_SOURCE_REMOVE
+}
+
+/// Pide a Nautilus que vuelva a consultar los archivos (sólo en el main loop).
+///
+/// Devuelve las URIs que Nautilus no tiene cargadas.
+pub unsafe fn invalidate_file_infos(uris: &[String]) -> Vec<String> {
+    let mut not_loaded = Vec::new();
+
+    for uri in uris {
+        let uri_c = str_to_cstring(uri);
+        let file = nautilus_file_info_lookup_for_uri(uri_c.as_ptr());
+        if file.is_null() {
+            // Nautilus no tiene el archivo cargado: no hay emblema que refrescar
+            not_loaded.push(uri.clone());
+            continue;
+        }
+        nautilus_file_info_invalidate_extension_info(file);
+        gobject_sys::g_object_unref(file as *mut gobject_sys::GObject);
+    }
+
+    not_loaded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gdrivexp_ipc_proto::{FolderSummary, SyncStatus};
+    use gdrivexp_mock_daemon::{Fixture, MockDaemon};
+    use std::time::Instant;
+
+    const ROOT: &str = "/srv/gdrivexp-subscription/Drive";
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn path_to_uri_escapes_like_gfile() {
+        assert_eq!(path_to_uri("/a/b c/ñ#1.txt"), "file:///a/b%20c/%C3%B1%231.txt");
+        assert_eq!(path_to_uri("file:///a/b%20c"), "file:///a/b%20c");
+    }
+
+    #[test]
+    fn status_change_reaches_subscription_and_evicts_caches() {
+        let file = format!("{}/Informes/2024 Q1.pdf", ROOT);
+        let daemon = MockDaemon::start(
+            Fixture::parse(&format!(
+                r#"
+                mount_points = ["{root}"]
+
+                [[file]]
+                path = "{file}"
+                status = {{ Uploading = {{ percent = 40 }} }}
+                "#,
+                root = ROOT,
+                file = file,
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let rt = runtime();
+        let client = IpcClient::with_socket_path(daemon.socket_path().to_path_buf());
+        let mut subscription = rt.block_on(client.subscribe()).expect("subscribe");
+
+        // El daemon registra la suscripción después de confirmar el Subscribe
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while daemon.subscribers() == 0 {
+            assert!(Instant::now() < deadline, "subscription never registered");
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let file_uri = format!("file://{}/Informes/2024%20Q1.pdf", ROOT);
+        let folder_uri = format!("file://{}/Informes", ROOT);
+        let root_uri = format!("file://{}", ROOT);
+        let other_uri = format!("file://{}/Fotos/playa.jpg", ROOT);
+        let other_folder_uri = format!("file://{}/Fotos", ROOT);
+        let data = crate::FileStatusData {
+            status: SyncStatus::Synced,
+            ..crate::FileStatusData::unknown()
+        };
+        for uri in [&file_uri, &folder_uri, &other_uri] {
+            status_cache::store(uri, &data);
+        }
+        for uri in [&folder_uri, &other_folder_uri] {
+            folder_summary::store(uri, FolderSummary { total: 1, synced: 1, ..Default::default() });
+        }
+
+        daemon.set_status(&file, SyncStatus::Synced);
+        let paths = rt
+            .block_on(async { tokio::time::timeout(Duration::from_secs(2), subscription.next_changes()).await })
+            .expect("no StatusChanged within 2 s")
+            .expect("subscription closed");
+        assert_eq!(paths, vec![file.clone()]);
+
+        let mut uris = evict_changed(&paths);
+        uris.sort();
+        let mut expected = vec![file_uri.clone(), folder_uri.clone(), root_uri];
+        expected.sort();
+        assert_eq!(uris, expected);
+
+        assert!(status_cache::lookup(&file_uri).is_none());
+        assert!(status_cache::lookup(&folder_uri).is_none());
+        assert!(folder_summary::lookup(&folder_uri).is_none());
+        // Lo que no contiene al archivo cambiado sigue en caché
+        assert!(status_cache::lookup(&other_uri).is_some());
+        assert!(folder_summary::lookup(&other_folder_uri).is_some());
+    }
+}
@@ -0,0 +1,61 @@
+//! Refresco periódico de archivos con una transferencia en curso
+//!
+//! Mientras un archivo está en `Uploading`/`Downloading` se invalida cada
+//! segundo para que su emblema de progreso avance aunque el daemon no notifique.
+//! Todo se ejecuta en el main loop de GLib.
+
+use crate::status_cache;
+use crate::SyncStatus;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Intervalo de refresco de los emblemas de progreso
+const REFRESH_INTERVAL_SECS: u32 = 1;
+
+static ACTIVE: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+static TIMER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Registra el último estado mostrado para una URI
+pub fn update(uri: &str, status: SyncStatus) {
+    let Ok(mut active) = ACTIVE.lock() else { return };
+    let active = active.get_or_insert_with(HashSet::new);
+
+    if status.is_transferring() {
+        active.insert(uri.to_string());
+        if !TIMER_RUNNING.swap(true, Ordering::AcqRel) {
+            unsafe {
+                glib_sys::g_timeout_add_seconds(REFRESH_INTERVAL_SECS, Some(refresh_tick), std::ptr::null_mut());
+            }
+        }
+    } else {
+        active.remove(uri);
+    }
+}
+
+unsafe extern "C" fn refresh_tick(_data: glib_sys::gpointer) -> glib_sys::gboolean {
+    let uris: Vec<String> = match ACTIVE.lock() {
+        Ok(active) => active.iter().flatten().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+
+    if uris.is_empty() {
+        TIMER_RUNNING.store(false, Ordering::Release);
+        return glib_sys::G_SOURCE_REMOVE;
+    }
+
+    // El progreso cambia aunque no haya evento: forzar una nueva consulta al daemon
+    status_cache::invalidate(&uris);
+    let not_loaded = crate::subscription::invalidate_file_infos(&uris);
+
+    // Archivos que Nautilus ya no muestra: dejar de seguirlos
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some(active) = active.as_mut() {
+            for uri in &not_loaded {
+                active.remove(uri);
+            }
+        }
+    }
+
+    glib_sys::G_SOURCE_CONTINUE
+}
@@ -0,0 +1,351 @@
+//! Cola de consultas entre Nautilus y el worker IPC
+//!
+//! Encolar nunca bloquea al llamador: la cola es una sección crítica corta más
+//! un `Notify` que despierta al worker. Las consultas de una misma URI se
+//! agrupan (en cola y en vuelo) y las más recientes se atienden primero, para
+//! que los archivos que Nautilus acaba de pedir (los visibles) reciban su
+//! emblema antes que los antiguos. Las entradas cuyos llamadores renunciaron
+//! se descartan sin preguntar al daemon.
+//!
+//! Ninguna consulta viva se responde como desconocida por falta de sitio: si
+//! la cola crece más allá de `MAX_QUEUED` sólo se podan las canceladas, y las
+//! demás esperan su turno con la prioridad más baja.
+
+use crate::FileStatusData;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Callback que el hilo del worker invoca con el resultado
+pub type StatusCallback = Box<dyn FnOnce(FileStatusData) + Send>;
+
+/// URIs distintas en cola a partir de las cuales se podan las canceladas
+const MAX_QUEUED: usize = 512;
+
+/// Alguien que espera el estado de una URI
+pub struct Waiter {
+    /// Se activa cuando el llamador ya no quiere la respuesta (Nautilus canceló
+    /// la actualización o una consulta síncrona agotó su timeout)
+    pub cancelled: Option<Arc<AtomicBool>>,
+    pub on_complete: StatusCallback,
+}
+
+impl Waiter {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.as_ref().is_some_and(|c| c.load(Ordering::Acquire))
+    }
+}
+
+/// URI entregada al worker
+pub struct Job {
+    pub uri: String,
+    /// Las carpetas reciben el estado agregado de su contenido en lugar del propio
+    pub is_directory: bool,
+}
+
+struct Entry {
+    /// Clave en `State::order`
+    seq: u64,
+    is_directory: bool,
+    waiters: Vec<Waiter>,
+}
+
+#[derive(Default)]
+struct State {
+    /// URIs en cola por número de secuencia, la más antigua primero; el worker
+    /// toma por el final. Volver a pedir una URI le da un número nuevo
+    order: BTreeMap<u64, String>,
+    next_seq: u64,
+    queued: HashMap<String, Entry>,
+    /// URIs enviadas al daemon y aún sin respuesta, por (uri, is_directory):
+    /// una carpeta necesita el estado agregado, no el propio que devuelve una consulta simple
+    in_flight: HashMap<(String, bool), Vec<Waiter>>,
+    /// Longitud de `order` que dispara la siguiente poda (el doble de lo que quedó tras la anterior)
+    prune_at: usize,
+    /// El hilo del worker terminó: responder en el acto
+    closed: bool,
+}
+
+impl State {
+    /// Quita de la cola las entradas cuyos llamadores renunciaron todos
+    fn prune_cancelled(&mut self) -> Vec<Waiter> {
+        let State { order, queued, .. } = self;
+        let mut pruned = Vec::new();
+        order.retain(|_, uri| {
+            let cancelled = queued
+                .get(uri)
+                .is_some_and(|entry| entry.waiters.iter().all(Waiter::is_cancelled));
+            if cancelled {
+                pruned.extend(queued.remove(uri).into_iter().flat_map(|entry| entry.waiters));
+            }
+            !cancelled
+        });
+        pruned
+    }
+}
+
+pub struct WorkQueue {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl WorkQueue {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Añade alguien que espera `uri` (desde cualquier hilo, sin esperar al worker)
+    pub fn push(&self, uri: &str, is_directory: bool, waiter: Waiter) {
+        let mut pruned = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.closed {
+                drop(state);
+                (waiter.on_complete)(FileStatusData::unknown());
+                return;
+            }
+
+            // Ya va camino del daemon: compartir esa respuesta. La agregada de una
+            // carpeta también sirve a una consulta simple, pero no al revés
+            let shareable: &[bool] = if is_directory { &[true] } else { &[true, false] };
+            for &in_flight_directory in shareable {
+                if let Some(waiters) = state.in_flight.get_mut(&(uri.to_string(), in_flight_directory)) {
+                    waiters.push(waiter);
+                    return;
+                }
+            }
+
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            let state = &mut *state;
+            match state.queued.get_mut(uri) {
+                Some(entry) => {
+                    entry.is_directory |= is_directory;
+                    entry.waiters.push(waiter);
+                    // Pedida otra vez: vuelve a ser reciente, pasa al principio de la fila
+                    let previous = std::mem::replace(&mut entry.seq, seq);
+                    let uri = state.order.remove(&previous).unwrap_or_else(|| uri.to_string());
+                    state.order.insert(seq, uri);
+                }
+                None => {
+                    state.queued.insert(
+                        uri.to_string(),
+                        Entry {
+                            seq,
+                            is_directory,
+                            waiters: vec![waiter],
+                        },
+                    );
+                    state.order.insert(seq, uri.to_string());
+                    // Las entradas vivas se quedan (las más antiguas son las últimas en salir)
+                    if state.order.len() > MAX_QUEUED.max(state.prune_at) {
+                        pruned = state.prune_cancelled();
+                        state.prune_at = state.order.len() * 2;
+                    }
+                }
+            }
+        }
+
+        if !pruned.is_empty() {
+            log_debug!("Work queue over {} URIs, pruned {} cancelled waiters", MAX_QUEUED, pruned.len());
+        }
+        for waiter in pruned {
+            (waiter.on_complete)(FileStatusData::unknown());
+        }
+        self.notify.notify_one();
+    }
+
+    /// Espera trabajo y toma hasta `max` URIs, las más recientes primero.
+    ///
+    /// Las URIs tomadas pasan a estar en vuelo; cada una debe responderse con `complete`.
+    pub async fn take(&self, max: usize) -> Vec<Job> {
+        loop {
+            let mut cancelled = Vec::new();
+            let jobs = {
+                let mut state = self.state.lock().unwrap();
+                let mut jobs = Vec::new();
+                while jobs.len() < max {
+                    let Some((_, uri)) = state.order.pop_last() else {
+                        break;
+                    };
+                    let Some(entry) = state.queued.remove(&uri) else {
+                        continue;
+                    };
+                    // Nadie quiere ya la respuesta: no preguntar al daemon
+                    if entry.waiters.iter().all(Waiter::is_cancelled) {
+                        cancelled.extend(entry.waiters);
+                        continue;
+                    }
+                    state.in_flight.insert((uri.clone(), entry.is_directory), entry.waiters);
+                    jobs.push(Job {
+                        uri,
+                        is_directory: entry.is_directory,
+                    });
+                }
+                if state.order.is_empty() {
+                    state.prune_at = 0;
+                }
+                jobs
+            };
+
+            if !cancelled.is_empty() {
+                log_debug!("Worker skipping {} cancelled waiters", cancelled.len());
+            }
+            for waiter in cancelled {
+                (waiter.on_complete)(FileStatusData::unknown());
+            }
+            if !jobs.is_empty() {
+                return jobs;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Entrega el estado de una URI en vuelo a todos los que la esperan
+    pub fn complete(&self, job: &Job, data: FileStatusData) {
+        let key = (job.uri.clone(), job.is_directory);
+        let waiters = self.state.lock().unwrap().in_flight.remove(&key).unwrap_or_default();
+        for waiter in waiters {
+            (waiter.on_complete)(data.clone());
+        }
+    }
+
+    /// El worker se detuvo: responder todo lo pendiente y cualquier consulta posterior
+    pub fn close(&self) {
+        let waiters: Vec<Waiter> = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            state.order.clear();
+            let queued = state.queued.drain().flat_map(|(_, entry)| entry.waiters);
+            let mut waiters: Vec<Waiter> = queued.collect();
+            waiters.extend(state.in_flight.drain().flat_map(|(_, waiters)| waiters));
+            waiters
+        };
+        for waiter in waiters {
+            (waiter.on_complete)(FileStatusData::unknown());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyncStatus;
+    use std::sync::mpsc;
+
+    fn take(queue: &WorkQueue) -> Vec<Job> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(queue.take(64))
+    }
+
+    fn waiter(tx: &mpsc::Sender<(&'static str, SyncStatus)>, name: &'static str) -> Waiter {
+        let tx = tx.clone();
+        Waiter {
+            cancelled: None,
+            on_complete: Box::new(move |data| tx.send((name, data.status)).unwrap()),
+        }
+    }
+
+    fn data(status: SyncStatus) -> FileStatusData {
+        FileStatusData { status, ..FileStatusData::unknown() }
+    }
+
+    #[test]
+    fn directory_waiter_does_not_share_a_raw_in_flight_answer() {
+        let queue = WorkQueue::new();
+        let (tx, rx) = mpsc::channel();
+
+        queue.push("file:///d", false, waiter(&tx, "plain"));
+        let plain = take(&queue);
+        queue.push("file:///d", true, waiter(&tx, "directory"));
+        let directory = take(&queue);
+        assert_eq!(directory.len(), 1);
+        assert!(directory[0].is_directory);
+
+        queue.complete(&plain[0], data(SyncStatus::Synced));
+        assert_eq!(rx.try_recv().unwrap(), ("plain", SyncStatus::Synced));
+        assert!(rx.try_recv().is_err());
+
+        queue.complete(&directory[0], data(SyncStatus::Error));
+        assert_eq!(rx.try_recv().unwrap(), ("directory", SyncStatus::Error));
+    }
+
+    #[test]
+    fn asking_again_moves_a_uri_to_the_front() {
+        let queue = WorkQueue::new();
+        let (tx, rx) = mpsc::channel();
+
+        for uri in ["file:///a", "file:///b", "file:///c", "file:///a"] {
+            queue.push(uri, false, waiter(&tx, "any"));
+        }
+        let jobs = take(&queue);
+        let uris: Vec<&str> = jobs.iter().map(|job| job.uri.as_str()).collect();
+        assert_eq!(uris, ["file:///a", "file:///c", "file:///b"]);
+
+        // Las dos esperas de la misma URI comparten la respuesta
+        queue.complete(&jobs[0], data(SyncStatus::Synced));
+        assert_eq!(rx.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn overflow_keeps_live_waiters_queued() {
+        let queue = WorkQueue::new();
+        let (tx, rx) = mpsc::channel();
+
+        let oldest_cancelled = Arc::new(AtomicBool::new(false));
+        queue.push(
+            "file:///cancelled",
+            false,
+            Waiter {
+                cancelled: Some(oldest_cancelled.clone()),
+                on_complete: {
+                    let tx = tx.clone();
+                    Box::new(move |data| tx.send(("cancelled", data.status)).unwrap())
+                },
+            },
+        );
+        oldest_cancelled.store(true, Ordering::Release);
+        queue.push("file:///oldest", false, waiter(&tx, "oldest"));
+        for i in 0..MAX_QUEUED {
+            queue.push(&format!("file:///f{}", i), false, waiter(&tx, "newer"));
+        }
+
+        // Sólo se responde (como desconocida) la que nadie espera ya
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [("cancelled", SyncStatus::Unknown)]);
+
+        // La más antigua sigue en cola, la última en salir
+        let mut taken = Vec::new();
+        loop {
+            let jobs = take(&queue);
+            let last = jobs.iter().any(|job| job.uri == "file:///oldest");
+            taken.extend(jobs);
+            if last {
+                break;
+            }
+        }
+        assert_eq!(taken.len(), MAX_QUEUED + 1);
+        assert_eq!(taken.last().unwrap().uri, "file:///oldest");
+        queue.complete(taken.last().unwrap(), data(SyncStatus::Synced));
+        assert_eq!(rx.try_recv().unwrap(), ("oldest", SyncStatus::Synced));
+    }
+
+    #[test]
+    fn plain_waiter_shares_an_aggregated_in_flight_answer() {
+        let queue = WorkQueue::new();
+        let (tx, rx) = mpsc::channel();
+
+        queue.push("file:///d", true, waiter(&tx, "directory"));
+        let jobs = take(&queue);
+        queue.push("file:///d", false, waiter(&tx, "plain"));
+
+        queue.complete(&jobs[0], data(SyncStatus::Error));
+        let answers: Vec<_> = rx.try_iter().collect();
+        assert_eq!(answers, [("directory", SyncStatus::Error), ("plain", SyncStatus::Error)]);
+    }
+}
@@ -0,0 +1,279 @@
+//! IpcClient contra el daemon simulado: reconexión, timeouts y compatibilidad
+
+use gdrivexp_ipc_proto::ConflictResolution;
+use gdrivexp_mock_daemon::{request_name, Fixture, MockDaemon};
+use gdrivexp_nautilus::ipc_client::IpcClient;
+use gdrivexp_nautilus::{FileAvailability, SyncStatus};
+use std::io;
+use std::time::Duration;
+
+const FILES: &str = r#"
+[[file]]
+path = "/srv/drive/informe.pdf"
+status = "Synced"
+availability = "LocalOnline"
+
+[[file]]
+path = "/srv/drive/foto.jpg"
+status = "CloudOnly"
+availability = "OnlineOnly"
+"#;
+
+const INFORME: &str = "/srv/drive/informe.pdf";
+const FOTO: &str = "/srv/drive/foto.jpg";
+
+/// Todas las capacidades salvo multiplexación: una petición cada vez por la conexión
+const SERIAL: &str = r#"capabilities = ["extended_status", "subscribe", "batch_status", "directory_status", "mount_points"]"#;
+
+fn start(header: &str, faults: &str) -> MockDaemon {
+    let text = format!("{}\n{}\n{}", header, FILES, faults);
+    MockDaemon::start(Fixture::parse(&text).expect("fixture")).expect("mock daemon")
+}
+
+fn connect(daemon: &MockDaemon) -> IpcClient {
+    IpcClient::with_socket_path(daemon.socket_path().to_path_buf())
+}
+
+/// Un runtime por prueba: las conexiones del cliente quedan ligadas a él, como en el worker
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Cuántas peticiones de cada tipo recibió el daemon (las `Tagged` cuentan por su contenido)
+fn count(daemon: &MockDaemon, name: &str) -> usize {
+    daemon.requests().iter().filter(|r| request_name(r) == name).count()
+}
+
+fn assert_reconnects_after(header: &str, action: &str) {
+    let daemon = start(
+        header,
+        &format!("[[fault]]\nrequest = \"GetFileStatus\"\naction = \"{}\"", action),
+    );
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    let data = rt.block_on(client.get_extended_status(INFORME)).expect("status");
+    assert_eq!(data.status, SyncStatus::Synced, "{} / {}", header, action);
+    assert_eq!(data.availability, FileAvailability::LocalOnline);
+
+    // Primer intento perdido, reintento por una conexión nueva con su propio handshake
+    assert_eq!(daemon.connections(), 2, "{} / {}", header, action);
+    assert_eq!(count(&daemon, "Hello"), 2);
+    assert_eq!(count(&daemon, "GetFileStatus"), 2);
+}
+
+#[test]
+fn reconnects_after_a_disconnect() {
+    assert_reconnects_after("", "disconnect");
+    assert_reconnects_after(SERIAL, "disconnect");
+}
+
+#[test]
+fn reconnects_after_a_malformed_frame() {
+    assert_reconnects_after("", "malformed");
+    assert_reconnects_after(SERIAL, "malformed");
+}
+
+#[test]
+fn reconnects_after_a_truncated_or_oversized_frame() {
+    assert_reconnects_after("", "truncated");
+    assert_reconnects_after(SERIAL, "oversized");
+}
+
+#[test]
+fn gives_up_after_two_attempts() {
+    let daemon = start("", "[[fault]]\nrequest = \"GetFileStatus\"\naction = \"disconnect\"\ntimes = 0");
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    let error = rt.block_on(client.get_extended_status(INFORME)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Other);
+    assert_eq!(count(&daemon, "GetFileStatus"), 2);
+    assert_eq!(daemon.connections(), 2);
+}
+
+/// Abandona la primera consulta por timeout y comprueba que la siguiente recibe su propia respuesta
+fn timeout_then_query(header: &str) -> MockDaemon {
+    let daemon = start(
+        header,
+        &format!("[[fault]]\nrequest = \"GetFileStatus\"\npath = \"{}\"\ndelay_ms = 300", INFORME),
+    );
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    rt.block_on(async {
+        let slow = tokio::time::timeout(Duration::from_millis(50), client.get_extended_status(INFORME)).await;
+        assert!(slow.is_err(), "the delayed answer arrived before the timeout");
+
+        let data = client.get_extended_status(FOTO).await.expect("status");
+        assert_eq!(data.status, SyncStatus::CloudOnly);
+        // La respuesta tardía no debe colarse en la consulta siguiente
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let data = client.get_extended_status(FOTO).await.expect("status");
+        assert_eq!(data.status, SyncStatus::CloudOnly);
+    });
+    daemon
+}
+
+#[test]
+fn delay_fault_hits_the_timeout_on_a_multiplexed_connection() {
+    let daemon = timeout_then_query("");
+    // La respuesta tardía se descarta por id: la conexión sigue sirviendo
+    assert_eq!(daemon.connections(), 1);
+}
+
+#[test]
+fn delay_fault_hits_the_timeout_on_a_serial_connection() {
+    let daemon = timeout_then_query(SERIAL);
+    // La petición abandonada desalinearía el stream: se abre otra conexión
+    assert_eq!(daemon.connections(), 2);
+}
+
+#[test]
+fn v1_daemon_falls_back_to_the_legacy_protocol() {
+    let daemon = start("protocol_version = 1\ncapabilities = []", "");
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    let data = rt.block_on(client.get_extended_status(FOTO)).expect("status");
+    assert_eq!(data.status, SyncStatus::CloudOnly);
+    // Sin ExtendedStatus la disponibilidad se pide aparte
+    assert_eq!(data.availability, FileAvailability::OnlineOnly);
+
+    let info = client.protocol_info().expect("connected");
+    assert_eq!(info.protocol_version, 1);
+    assert!(info.capabilities.is_empty());
+    assert!(!client.is_multiplexed());
+
+    // El daemon rechaza el Hello con un Error; no hace falta reconectar
+    assert_eq!(daemon.connections(), 1);
+    let names: Vec<&str> = daemon.requests().iter().map(request_name).collect();
+    assert_eq!(names, ["Hello", "GetFileStatus", "GetFileAvailability"]);
+
+    // Lo que un daemon v1 no conoce ni se le pregunta
+    let error = rt.block_on(client.get_extended_status_batch(&[INFORME.to_string()])).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    assert_eq!(count(&daemon, "GetFileStatusBatch"), 0);
+}
+
+#[test]
+fn v3_daemon_statuses_are_decoded() {
+    let daemon = start("protocol_version = 3", "");
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    let batch = rt.block_on(client.get_extended_status_batch(&[INFORME.to_string(), FOTO.to_string()])).expect("batch");
+    let statuses: Vec<SyncStatus> = batch.iter().map(|data| data.status).collect();
+    assert_eq!(statuses, [SyncStatus::Synced, SyncStatus::CloudOnly]);
+    assert_eq!(client.protocol_info().unwrap().protocol_version, 3);
+    // Tagged sólo existe desde v4
+    assert!(!client.is_multiplexed());
+}
+
+#[test]
+fn newer_daemon_is_rejected_instead_of_misdecoded() {
+    let daemon = start("protocol_version = 5", "");
+    let client = connect(&daemon);
+    let rt = runtime();
+
+    let error = rt.block_on(client.get_extended_status(INFORME)).unwrap_err();
+    assert_eq!(error.to_string(), "Protocolo del daemon incompatible");
+    assert!(!client.protocol_info().unwrap().is_compatible());
+    assert!(rt.block_on(client.subscribe()).is_err());
+    // Nada más que el handshake: sus respuestas podrían tener otro formato
+    let names: Vec<&str> = daemon.requests().iter().map(request_name).collect();
+    assert_eq!(names, ["Hello", "Hello"]);
+}
+
+#[test]
+fn batch_falls_back_to_single_queries() {
+    let paths = [INFORME.to_string(), FOTO.to_string()];
+
+    // Daemon sin batch_status: error Unsupported sin enviar el lote
+    let daemon = start(r#"capabilities = ["extended_status"]"#, "");
+    let client = connect(&daemon);
+    let rt = runtime();
+    let error = rt.block_on(client.get_extended_status_batch(&paths)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    assert_eq!(count(&daemon, "GetFileStatusBatch"), 0);
+    for path in &paths {
+        assert!(rt.block_on(client.get_extended_status(path)).is_ok());
+    }
+    assert_eq!(count(&daemon, "GetFileStatus"), 2);
+
+    // Lote rechazado por el daemon: el error llega al llamador y las consultas sueltas funcionan
+    let daemon = start("", "[[fault]]\nrequest = \"GetFileStatusBatch\"\naction = \"error\"\nmessage = \"Lote no disponible\"");
+    let client = connect(&daemon);
+    let rt = runtime();
+    let error = rt.block_on(client.get_extended_status_batch(&paths)).unwrap_err();
+    assert_eq!(error.to_string(), "Lote no disponible");
+    let data = rt.block_on(client.get_extended_status(FOTO)).expect("status");
+    assert_eq!(data.status, SyncStatus::CloudOnly);
+    assert_eq!(daemon.connections(), 1);
+}
+
+#[test]
+fn conflict_resolution_reports_the_daemon_rejection() {
+    let daemon = start(
+        "",
+        &format!("[[fault]]\nrequest = \"ResolveConflict\"\naction = \"error\"\nmessage = \"Sin conexión con Drive\"\npath = \"{}\"", FOTO),
+    );
+    let client = connect(&daemon);
+    let rt = runtime();
+    let error = rt.block_on(client.resolve_conflict(FOTO, ConflictResolution::KeepBoth)).unwrap_err();
+    assert_eq!(error.to_string(), "Sin conexión con Drive");
+    assert!(rt.block_on(client.resolve_conflict(FOTO, ConflictResolution::KeepBoth)).expect("resolved"));
+
+    // Sin CAP_CONFLICTS ni se envía
+    let daemon = start(r#"capabilities = ["extended_status"]"#, "");
+    let client = connect(&daemon);
+    let error = rt.block_on(client.resolve_conflict(FOTO, ConflictResolution::KeepLocal)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    assert_eq!(count(&daemon, "ResolveConflict"), 0);
+}
+
+#[test]
+fn retry_sync_reports_the_daemon_rejection() {
+    let daemon = start(
+        "",
+        "[[fault]]\nrequest = \"RetrySync\"\naction = \"error\"\nmessage = \"Cuota de Drive agotada\"",
+    );
+    let client = connect(&daemon);
+    let rt = runtime();
+    let paths = [INFORME.to_string()];
+    let error = rt.block_on(client.retry_sync(&paths)).unwrap_err();
+    assert_eq!(error.to_string(), "Cuota de Drive agotada");
+    assert!(rt.block_on(client.retry_sync(&paths)).expect("retried"));
+}
+
+#[test]
+fn folder_actions_report_the_daemon_rejection() {
+    let daemon = start(
+        "",
+        "[[fault]]\nrequest = \"SyncNow\"\naction = \"error\"\nmessage = \"Sincronización en pausa\"\n\n\
+         [[fault]]\nrequest = \"PauseSync\"\naction = \"error\"\nmessage = \"Carpeta no rastreada\"",
+    );
+    let client = connect(&daemon);
+    let rt = runtime();
+    let error = rt.block_on(client.sync_now("/srv/drive")).unwrap_err();
+    assert_eq!(error.to_string(), "Sincronización en pausa");
+    let error = rt.block_on(client.pause_sync("/srv/drive")).unwrap_err();
+    assert_eq!(error.to_string(), "Carpeta no rastreada");
+
+    assert!(rt.block_on(client.sync_now("/srv/drive")).expect("synced"));
+    assert!(rt.block_on(client.pause_sync("/srv/drive")).expect("paused"));
+}
+
+#[test]
+fn unavailable_daemon_is_reported_without_panicking() {
+    let daemon = start("", "");
+    let client = connect(&daemon);
+    let rt = runtime();
+    drop(daemon);
+
+    let error = rt.block_on(client.get_extended_status(INFORME)).unwrap_err();
+    assert_eq!(error.to_string(), "Daemon no disponible");
+}
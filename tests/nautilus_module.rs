@@ -0,0 +1,932 @@
+//! La extensión cargada como la carga Nautilus, contra un libnautilus-extension simulado
+//!
+//! Este ejecutable implementa las funciones de libnautilus-extension que usa la
+//! extensión (se enlaza con `--export-dynamic`, ver build.rs), abre
+//! `libgdrivexp_nautilus.so` con dlopen y la recorre por sus símbolos exportados
+//! y las vtables de sus interfaces, con el daemon simulado detrás.
+
+use gdrivexp_ipc_proto::{ConflictResolution, IpcRequest};
+use gdrivexp_mock_daemon::{Fixture, MockDaemon};
+use gdrivexp_nautilus::SyncStatus;
+use glib_sys::{gboolean, gpointer, GList, GType, GFALSE, GTRUE};
+use gobject_sys::{GClosure, GObject, GObjectClass, GParamSpec, GTypeInterface, GTypeModule, GTypeModuleClass, GValue};
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_uint};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ROOT: &str = "/srv/drive";
+
+const FIXTURE: &str = r#"
+mount_points = ["/srv/drive"]
+
+[[file]]
+path = "/srv/drive"
+status = "Synced"
+
+[[file]]
+path = "/srv/drive/informe.pdf"
+status = "Synced"
+shared = true
+owner = "ana@example.com"
+
+[[file]]
+path = "/srv/drive/proyecto"
+status = "Synced"
+
+[[file]]
+path = "/srv/drive/proyecto/datos.csv"
+status = "Error"
+
+[[file]]
+path = "/srv/drive/borrador.txt"
+status = "LocalOnly"
+
+[[file]]
+path = "/srv/drive/presupuesto.xlsx"
+status = "Synced"
+
+[[file]]
+path = "/srv/drive/foto.jpg"
+status = "CloudOnly"
+availability = "OnlineOnly"
+
+[[file]]
+path = "/srv/drive/plano.dwg"
+status = "Conflict"
+
+[[file]]
+path = "/srv/drive/notas.txt"
+status = "Error"
+error = "Cuota\u0000excedida"
+
+[[file]]
+path = "/srv/drive/contrato.pdf"
+status = "Synced"
+drive_id = "1AbC"
+
+[[file]]
+path = "/srv/drive/avisos.txt"
+status = "Synced"
+"#;
+
+// ============================================================
+// libnautilus-extension simulado
+// ============================================================
+
+/// NautilusOperationResult
+const COMPLETE: c_int = 0;
+const IN_PROGRESS: c_int = 2;
+
+#[repr(C)]
+struct InfoProviderInterface {
+    g_iface: GTypeInterface,
+    update_file_info: Option<unsafe extern "C" fn(*mut GObject, *mut GObject, *mut GClosure, *mut gpointer) -> c_int>,
+    cancel_update: Option<unsafe extern "C" fn(*mut GObject, gpointer)>,
+}
+
+#[repr(C)]
+struct MenuProviderInterface {
+    g_iface: GTypeInterface,
+    get_file_items: Option<unsafe extern "C" fn(*mut GObject, *mut GList) -> *mut GList>,
+    get_background_items: Option<unsafe extern "C" fn(*mut GObject, *mut GObject) -> *mut GList>,
+}
+
+#[repr(C)]
+struct ColumnProviderInterface {
+    g_iface: GTypeInterface,
+    get_columns: Option<unsafe extern "C" fn(*mut GObject) -> *mut GList>,
+}
+
+#[repr(C)]
+struct PropertiesModelProviderInterface {
+    g_iface: GTypeInterface,
+    get_models: Option<unsafe extern "C" fn(*mut GObject, *mut GList) -> *mut GList>,
+}
+
+/// GTypes que en Nautilus registra libnautilus-extension
+struct Types {
+    info_provider: GType,
+    menu_provider: GType,
+    column_provider: GType,
+    properties_model_provider: GType,
+    menu_item: GType,
+    properties_item: GType,
+}
+
+fn types() -> &'static Types {
+    static TYPES: OnceLock<Types> = OnceLock::new();
+    TYPES.get_or_init(|| unsafe {
+        Types {
+            info_provider: register_interface("NautilusInfoProvider", size_of::<InfoProviderInterface>()),
+            menu_provider: register_interface("NautilusMenuProvider", size_of::<MenuProviderInterface>()),
+            column_provider: register_interface("NautilusColumnProvider", size_of::<ColumnProviderInterface>()),
+            properties_model_provider: register_interface(
+                "NautilusPropertiesModelProvider",
+                size_of::<PropertiesModelProviderInterface>(),
+            ),
+            menu_item: register_object("NautilusMenuItem", Some(menu_item_class_init)),
+            properties_item: register_object("NautilusPropertiesItem", None),
+        }
+    })
+}
+
+unsafe fn register_interface(name: &str, size: usize) -> GType {
+    let name = CString::new(name).unwrap();
+    let gtype = gobject_sys::g_type_register_static_simple(
+        gobject_sys::G_TYPE_INTERFACE,
+        name.as_ptr(),
+        size as c_uint,
+        None,
+        0,
+        None,
+        0,
+    );
+    gobject_sys::g_type_interface_add_prerequisite(gtype, gobject_sys::G_TYPE_OBJECT);
+    gtype
+}
+
+unsafe fn register_object(name: &str, class_init: gobject_sys::GClassInitFunc) -> GType {
+    let name = CString::new(name).unwrap();
+    gobject_sys::g_type_register_static_simple(
+        gobject_sys::G_TYPE_OBJECT,
+        name.as_ptr(),
+        size_of::<GObjectClass>() as c_uint,
+        class_init,
+        size_of::<GObject>() as c_uint,
+        None,
+        0,
+    )
+}
+
+/// Un NautilusFileInfo: la extensión sólo lo usa a través de las funciones de abajo
+#[derive(Debug, Default, Clone)]
+struct FakeFile {
+    uri: String,
+    is_directory: bool,
+    emblems: Vec<String>,
+    attributes: BTreeMap<String, String>,
+    invalidations: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+struct FakeMenuItem {
+    name: String,
+    label: String,
+    tip: String,
+    sensitive: bool,
+    submenu: Option<usize>,
+}
+
+/// Lo que la extensión ha creado o modificado, por dirección del objeto
+#[derive(Default)]
+struct Registry {
+    files: BTreeMap<usize, FakeFile>,
+    menu_items: BTreeMap<usize, FakeMenuItem>,
+    menus: BTreeMap<usize, Vec<usize>>,
+    columns: BTreeMap<usize, [String; 4]>,
+    models: BTreeMap<usize, (String, usize)>,
+    properties_items: BTreeMap<usize, (String, String)>,
+    /// Llamadas a update_complete: (closure, handle, resultado)
+    completed: Vec<(usize, usize, c_int)>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> T {
+    let mut guard = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(Registry::default))
+}
+
+unsafe fn to_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+unsafe fn g_strdup(text: &str) -> *mut c_char {
+    let text = CString::new(text).unwrap();
+    glib_sys::g_strdup(text.as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_get_uri(file: *mut GObject) -> *mut c_char {
+    match with_registry(|r| r.files.get(&(file as usize)).map(|f| f.uri.clone())) {
+        Some(uri) => g_strdup(&uri),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_is_directory(file: *mut GObject) -> gboolean {
+    with_registry(|r| r.files.get(&(file as usize)).is_some_and(|f| f.is_directory)) as gboolean
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_add_emblem(file: *mut GObject, emblem_name: *const c_char) {
+    let emblem = to_string(emblem_name);
+    with_registry(|r| r.files.get_mut(&(file as usize)).expect("unknown file").emblems.push(emblem));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_add_string_attribute(
+    file: *mut GObject,
+    attribute_name: *const c_char,
+    value: *const c_char,
+) {
+    let (name, value) = (to_string(attribute_name), to_string(value));
+    with_registry(|r| r.files.get_mut(&(file as usize)).expect("unknown file").attributes.insert(name, value));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_lookup_for_uri(uri: *const c_char) -> *mut GObject {
+    let uri = to_string(uri);
+    // Como Nautilus: el último archivo cargado con esa URI, con una referencia nueva
+    let file = with_registry(|r| r.files.iter().rev().find(|(_, f)| f.uri == uri).map(|(&address, _)| address));
+    match file {
+        Some(address) => gobject_sys::g_object_ref(address as *mut GObject),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_file_info_invalidate_extension_info(file: *mut GObject) {
+    with_registry(|r| r.files.get_mut(&(file as usize)).expect("unknown file").invalidations += 1);
+}
+
+#[no_mangle]
+pub extern "C" fn nautilus_info_provider_get_type() -> GType {
+    types().info_provider
+}
+
+#[no_mangle]
+pub extern "C" fn nautilus_menu_provider_get_type() -> GType {
+    types().menu_provider
+}
+
+#[no_mangle]
+pub extern "C" fn nautilus_column_provider_get_type() -> GType {
+    types().column_provider
+}
+
+#[no_mangle]
+pub extern "C" fn nautilus_properties_model_provider_get_type() -> GType {
+    types().properties_model_provider
+}
+
+#[no_mangle]
+pub extern "C" fn nautilus_properties_item_get_type() -> GType {
+    types().properties_item
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_info_provider_update_complete_invoke(
+    update_complete: *mut GClosure,
+    _provider: *mut GObject,
+    handle: gpointer,
+    result: c_int,
+) {
+    with_registry(|r| r.completed.push((update_complete as usize, handle as usize, result)));
+}
+
+const PROP_SENSITIVE: c_uint = 1;
+
+unsafe extern "C" fn menu_item_class_init(class: gpointer, _data: gpointer) {
+    let object_class = class as *mut GObjectClass;
+    (*object_class).set_property = Some(menu_item_set_property);
+    (*object_class).get_property = Some(menu_item_get_property);
+
+    let name = CString::new("sensitive").unwrap();
+    let pspec = gobject_sys::g_param_spec_boolean(
+        name.as_ptr(),
+        ptr::null(),
+        ptr::null(),
+        GTRUE,
+        gobject_sys::G_PARAM_READWRITE,
+    );
+    gobject_sys::g_object_class_install_property(object_class, PROP_SENSITIVE, pspec);
+
+    let signal = CString::new("activate").unwrap();
+    gobject_sys::g_signal_new(
+        signal.as_ptr(),
+        (*(class as *mut gobject_sys::GTypeClass)).g_type,
+        gobject_sys::G_SIGNAL_RUN_LAST,
+        0,
+        None,
+        ptr::null_mut(),
+        None,
+        gobject_sys::G_TYPE_NONE,
+        0,
+    );
+}
+
+unsafe extern "C" fn menu_item_set_property(
+    object: *mut GObject,
+    property_id: c_uint,
+    value: *mut GValue,
+    _pspec: *mut GParamSpec,
+) {
+    if property_id == PROP_SENSITIVE {
+        let sensitive = gobject_sys::g_value_get_boolean(value) != GFALSE;
+        with_registry(|r| r.menu_items.entry(object as usize).or_default().sensitive = sensitive);
+    }
+}
+
+unsafe extern "C" fn menu_item_get_property(
+    object: *mut GObject,
+    property_id: c_uint,
+    value: *mut GValue,
+    _pspec: *mut GParamSpec,
+) {
+    if property_id == PROP_SENSITIVE {
+        let sensitive = with_registry(|r| r.menu_items.get(&(object as usize)).is_some_and(|i| i.sensitive));
+        gobject_sys::g_value_set_boolean(value, sensitive as gboolean);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_menu_item_new(
+    name: *const c_char,
+    label: *const c_char,
+    tip: *const c_char,
+    _icon: *const c_char,
+) -> *mut GObject {
+    let item = gobject_sys::g_object_new(types().menu_item, ptr::null());
+    let entry = FakeMenuItem {
+        name: to_string(name),
+        label: to_string(label),
+        tip: to_string(tip),
+        sensitive: true,
+        submenu: None,
+    };
+    with_registry(|r| r.menu_items.insert(item as usize, entry));
+    item
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_menu_new() -> *mut GObject {
+    let menu = gobject_sys::g_object_new(gobject_sys::G_TYPE_OBJECT, ptr::null());
+    with_registry(|r| r.menus.insert(menu as usize, Vec::new()));
+    menu
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_menu_append_item(menu: *mut GObject, item: *mut GObject) {
+    gobject_sys::g_object_ref(item);
+    with_registry(|r| r.menus.get_mut(&(menu as usize)).expect("unknown menu").push(item as usize));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_menu_item_set_submenu(item: *mut GObject, menu: *mut GObject) {
+    gobject_sys::g_object_ref(menu);
+    with_registry(|r| r.menu_items.get_mut(&(item as usize)).expect("unknown item").submenu = Some(menu as usize));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_column_new(
+    name: *const c_char,
+    attribute: *const c_char,
+    label: *const c_char,
+    description: *const c_char,
+) -> *mut GObject {
+    let column = gobject_sys::g_object_new(gobject_sys::G_TYPE_OBJECT, ptr::null());
+    let entry = [to_string(name), to_string(attribute), to_string(label), to_string(description)];
+    with_registry(|r| r.columns.insert(column as usize, entry));
+    column
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_properties_model_new(title: *const c_char, model: gpointer) -> *mut GObject {
+    let properties_model = gobject_sys::g_object_new(gobject_sys::G_TYPE_OBJECT, ptr::null());
+    let title = to_string(title);
+    // Toma posesión de `model`, como el real
+    with_registry(|r| r.models.insert(properties_model as usize, (title, model as usize)));
+    properties_model
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nautilus_properties_item_new(name: *const c_char, value: *const c_char) -> *mut GObject {
+    let item = gobject_sys::g_object_new(types().properties_item, ptr::null());
+    let entry = (to_string(name), to_string(value));
+    with_registry(|r| r.properties_items.insert(item as usize, entry));
+    item
+}
+
+// ============================================================
+// Carga de la extensión
+// ============================================================
+
+type ModuleInitialize = unsafe extern "C" fn(*mut GTypeModule);
+type ModuleListTypes = unsafe extern "C" fn(*mut *const GType, *mut c_int);
+
+static INITIALIZE: OnceLock<ModuleInitialize> = OnceLock::new();
+
+/// Como el NautilusModule de Nautilus: la extensión se inicializa al cargar el módulo
+unsafe extern "C" fn module_class_init(class: gpointer, _data: gpointer) {
+    let class = class as *mut GTypeModuleClass;
+    (*class).load = Some(module_load);
+    (*class).unload = Some(module_unload);
+}
+
+unsafe extern "C" fn module_load(module: *mut GTypeModule) -> gboolean {
+    INITIALIZE.get().expect("extension not loaded")(module);
+    GTRUE
+}
+
+unsafe extern "C" fn module_unload(_module: *mut GTypeModule) {}
+
+struct Harness {
+    daemon: MockDaemon,
+    provider: *mut GObject,
+    provider_type: GType,
+    list_types: ModuleListTypes,
+}
+
+// Sólo se usa con el mutex tomado: las pruebas comparten el main context de GLib
+unsafe impl Send for Harness {}
+
+/// Carga la extensión una vez para todo el ejecutable y serializa las pruebas
+fn harness() -> MutexGuard<'static, Harness> {
+    static HARNESS: OnceLock<Mutex<Harness>> = OnceLock::new();
+    HARNESS
+        .get_or_init(|| Mutex::new(unsafe { load_extension() }))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+unsafe fn load_extension() -> Harness {
+    // Un g_critical o g_warning de la extensión es un fallo de la prueba
+    glib_sys::g_log_set_always_fatal(glib_sys::G_LOG_LEVEL_CRITICAL | glib_sys::G_LOG_LEVEL_WARNING);
+
+    let daemon = MockDaemon::start(Fixture::parse(FIXTURE).expect("fixture")).expect("mock daemon");
+    // config::load() se ejecuta en nautilus_module_initialize
+    std::env::set_var("GDRIVEXP_NAUTILUS_CONFIG", daemon.socket_path().with_file_name("nautilus.toml"));
+    std::env::set_var("GDRIVEXP_NAUTILUS_SOCKET", daemon.socket_path());
+    // Las consultas síncronas del menú no deben depender de la carga de la máquina
+    std::env::set_var("GDRIVEXP_NAUTILUS_QUERY_TIMEOUT_MS", "2000");
+
+    // La que cargo acaba de compilar para esta prueba: target/debug/deps, junto al
+    // ejecutable (target/debug sólo se actualiza con cargo build)
+    let exe = std::env::current_exe().unwrap();
+    let library = exe.parent().unwrap().join(format!(
+        "{}gdrivexp_nautilus{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    ));
+    let library_c = CString::new(library.to_str().unwrap()).unwrap();
+    // Como g_module_open en Nautilus: símbolos locales, libnautilus-extension ya en el proceso
+    let handle = libc::dlopen(library_c.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+    assert!(!handle.is_null(), "dlopen {}: {}", library.display(), to_string(libc::dlerror()));
+
+    let symbol = |name: &str| {
+        let name_c = CString::new(name).unwrap();
+        let address = libc::dlsym(handle, name_c.as_ptr());
+        assert!(!address.is_null(), "missing symbol {}", name);
+        address
+    };
+    let initialize: ModuleInitialize = std::mem::transmute(symbol("nautilus_module_initialize"));
+    let list_types: ModuleListTypes = std::mem::transmute(symbol("nautilus_module_list_types"));
+    symbol("nautilus_module_shutdown");
+    INITIALIZE.set(initialize).ok();
+
+    let module_type = gobject_sys::g_type_register_static_simple(
+        gobject_sys::g_type_module_get_type(),
+        c"FakeNautilusModule".as_ptr(),
+        size_of::<GTypeModuleClass>() as c_uint,
+        Some(module_class_init),
+        size_of::<GTypeModule>() as c_uint,
+        None,
+        0,
+    );
+    let module = gobject_sys::g_object_new(module_type, ptr::null()) as *mut GTypeModule;
+    assert_eq!(gobject_sys::g_type_module_use(module), GTRUE);
+
+    let (types, count) = listed_types(list_types);
+    assert_eq!(count, 1);
+    let provider_type = types[0];
+    let provider = gobject_sys::g_object_new(provider_type, ptr::null());
+
+    Harness { daemon, provider, provider_type, list_types }
+}
+
+unsafe fn listed_types(list_types: ModuleListTypes) -> (Vec<GType>, c_int) {
+    let mut types: *const GType = ptr::null();
+    let mut count: c_int = 0;
+    list_types(&mut types, &mut count);
+    (std::slice::from_raw_parts(types, count.max(0) as usize).to_vec(), count)
+}
+
+// ============================================================
+// Utilidades de las pruebas
+// ============================================================
+
+impl Harness {
+    unsafe fn interface<T>(&self, iface_type: GType) -> &T {
+        let class = (*(self.provider as *mut gobject_sys::GTypeInstance)).g_class;
+        let iface = gobject_sys::g_type_interface_peek(class as gpointer, iface_type);
+        assert!(!iface.is_null(), "{} not implemented", type_name(iface_type));
+        &*(iface as *const T)
+    }
+
+    /// update_file_info; devuelve el resultado y el handle
+    unsafe fn update_file_info(&self, file: *mut GObject, closure: *mut GClosure) -> (c_int, usize) {
+        let iface: &InfoProviderInterface = self.interface(types().info_provider);
+        let mut handle: gpointer = ptr::null_mut();
+        let result = iface.update_file_info.unwrap()(self.provider, file, closure, &mut handle);
+        (result, handle as usize)
+    }
+
+    /// update_file_info hasta el final, sea inmediato o en el main loop
+    unsafe fn emblems_after_update(&self, file: *mut GObject) -> Vec<String> {
+        let closure = closure();
+        match self.update_file_info(file, closure) {
+            (COMPLETE, _) => {}
+            (IN_PROGRESS, handle) => assert!(pump_until(|| completion(closure) == Some((handle, COMPLETE)))),
+            (result, _) => panic!("unexpected result {}", result),
+        }
+        fake_file(file).emblems
+    }
+
+    unsafe fn file_items(&self, files: &[*mut GObject]) -> Vec<*mut GObject> {
+        let iface: &MenuProviderInterface = self.interface(types().menu_provider);
+        with_list(files, |list| take_list(iface.get_file_items.unwrap()(self.provider, list)))
+    }
+
+    unsafe fn background_items(&self, folder: *mut GObject) -> Vec<*mut GObject> {
+        let iface: &MenuProviderInterface = self.interface(types().menu_provider);
+        take_list(iface.get_background_items.unwrap()(self.provider, folder))
+    }
+
+    /// Espera a que el daemon reciba una petición que cumpla `matches`
+    fn wait_request(&self, matches: impl Fn(&IpcRequest) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if self.daemon.requests().iter().any(&matches) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        false
+    }
+}
+
+fn type_name(gtype: GType) -> String {
+    unsafe { to_string(gobject_sys::g_type_name(gtype)) }
+}
+
+/// Un NautilusFileInfo nuevo para `path`
+fn file(path: &str, is_directory: bool) -> *mut GObject {
+    let file = unsafe { gobject_sys::g_object_new(gobject_sys::G_TYPE_OBJECT, ptr::null()) };
+    let entry = FakeFile {
+        uri: format!("file://{}", path),
+        is_directory,
+        ..FakeFile::default()
+    };
+    with_registry(|r| r.files.insert(file as usize, entry));
+    file
+}
+
+fn fake_file(file: *mut GObject) -> FakeFile {
+    with_registry(|r| r.files[&(file as usize)].clone())
+}
+
+fn menu_item(item: *mut GObject) -> FakeMenuItem {
+    with_registry(|r| r.menu_items[&(item as usize)].clone())
+}
+
+/// El closure update_complete; la extensión sólo lo guarda y lo pasa a update_complete_invoke
+fn closure() -> *mut GClosure {
+    unsafe extern "C" fn never_called() {}
+    unsafe { gobject_sys::g_cclosure_new(Some(never_called), ptr::null_mut(), None) }
+}
+
+/// Handle y resultado con que la extensión invocó `closure`, si lo hizo
+fn completion(closure: *mut GClosure) -> Option<(usize, c_int)> {
+    with_registry(|r| {
+        r.completed
+            .iter()
+            .find(|(c, _, _)| *c == closure as usize)
+            .map(|&(_, handle, result)| (handle, result))
+    })
+}
+
+/// Itera el main context (idles de la extensión) hasta que `done` o 5 s
+fn pump_until(mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        pump();
+        if done() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+fn pump() {
+    unsafe { while glib_sys::g_main_context_iteration(ptr::null_mut(), GFALSE) != GFALSE {} }
+}
+
+unsafe fn with_list<T>(objects: &[*mut GObject], f: impl FnOnce(*mut GList) -> T) -> T {
+    let mut list = ptr::null_mut();
+    for &object in objects {
+        list = glib_sys::g_list_append(list, object as gpointer);
+    }
+    let result = f(list);
+    glib_sys::g_list_free(list);
+    result
+}
+
+/// Los elementos de una lista devuelta por la extensión (las referencias pasan al llamador)
+unsafe fn take_list(list: *mut GList) -> Vec<*mut GObject> {
+    let mut objects = Vec::new();
+    let mut node = list;
+    while !node.is_null() {
+        objects.push((*node).data as *mut GObject);
+        node = (*node).next;
+    }
+    glib_sys::g_list_free(list);
+    objects
+}
+
+fn names(items: &[*mut GObject]) -> Vec<String> {
+    items.iter().map(|&item| menu_item(item).name).collect()
+}
+
+fn find(items: &[*mut GObject], name: &str) -> *mut GObject {
+    *items.iter().find(|&&item| menu_item(item).name == name).unwrap_or_else(|| panic!("no {} item", name))
+}
+
+fn activate(item: *mut GObject) {
+    unsafe { gobject_sys::g_signal_emit_by_name(item, c"activate".as_ptr()) };
+}
+
+/// Nautilus suelta los items tras mostrar el menú; también libera el user_data de las señales
+fn release(items: Vec<*mut GObject>) {
+    for item in items {
+        unsafe { gobject_sys::g_object_unref(item) };
+    }
+}
+
+fn path(name: &str) -> String {
+    format!("{}/{}", ROOT, name)
+}
+
+/// La extensión envía URIs; el daemon acepta paths y URIs
+fn is(request_path: &str, name: &str) -> bool {
+    request_path == path(name) || request_path == format!("file://{}", path(name))
+}
+
+// ============================================================
+// Pruebas
+// ============================================================
+
+#[test]
+fn module_exports_one_provider_with_every_interface() {
+    let harness = harness();
+    let (types_listed, count) = unsafe { listed_types(harness.list_types) };
+    assert_eq!(count, 1);
+    assert_eq!(types_listed, [harness.provider_type]);
+    assert_eq!(type_name(harness.provider_type), "GDriveXPProviderFixed");
+
+    let t = types();
+    for iface in [t.info_provider, t.menu_provider, t.column_provider, t.properties_model_provider] {
+        let implemented = unsafe { gobject_sys::g_type_is_a(harness.provider_type, iface) };
+        assert_eq!(implemented, GTRUE, "{} not implemented", type_name(iface));
+    }
+
+    unsafe {
+        let info: &InfoProviderInterface = harness.interface(t.info_provider);
+        assert!(info.update_file_info.is_some() && info.cancel_update.is_some());
+        let menu: &MenuProviderInterface = harness.interface(t.menu_provider);
+        assert!(menu.get_file_items.is_some() && menu.get_background_items.is_some());
+        let columns: &ColumnProviderInterface = harness.interface(t.column_provider);
+        assert!(columns.get_columns.is_some());
+        let properties: &PropertiesModelProviderInterface = harness.interface(t.properties_model_provider);
+        assert!(properties.get_models.is_some());
+    }
+}
+
+#[test]
+fn update_file_info_adds_emblems_and_attributes_in_the_main_loop() {
+    let harness = harness();
+    let informe = file(&path("informe.pdf"), false);
+    let closure = closure();
+
+    let (result, handle) = unsafe { harness.update_file_info(informe, closure) };
+    assert_eq!(result, IN_PROGRESS);
+    assert_ne!(handle, 0);
+    // Nada se aplica fuera del main loop
+    assert!(fake_file(informe).emblems.is_empty());
+    assert_eq!(completion(closure), None);
+
+    assert!(pump_until(|| completion(closure).is_some()), "update_complete never invoked");
+    assert_eq!(completion(closure), Some((handle, COMPLETE)));
+    let informe = fake_file(informe);
+    assert_eq!(informe.emblems, ["emblem-shared", "emblem-gdrivexp-synced"]);
+    assert_eq!(informe.attributes["gdrivexp_status"], "Synced");
+    assert_eq!(informe.attributes["gdrivexp_owner"], "ana@example.com");
+    assert_eq!(informe.attributes["gdrivexp_shared"], "Yes");
+
+    // Otra vista del mismo archivo: respuesta inmediata desde la caché
+    let again = file(&path("informe.pdf"), false);
+    let (result, _) = unsafe { harness.update_file_info(again, closure) };
+    assert_eq!(result, COMPLETE);
+    assert_eq!(fake_file(again).emblems, ["emblem-shared", "emblem-gdrivexp-synced"]);
+
+    // Con los puntos de montaje ya conocidos, lo de fuera no llega al daemon
+    let outside = file("/home/ana/otro.txt", false);
+    let (result, _) = unsafe { harness.update_file_info(outside, closure) };
+    assert_eq!(result, COMPLETE);
+    assert!(fake_file(outside).emblems.is_empty());
+    let asked = harness.daemon.requests().iter().any(|r| format!("{:?}", r).contains("otro.txt"));
+    assert!(!asked);
+}
+
+#[test]
+fn folder_emblem_reflects_its_contents() {
+    let harness = harness();
+    let proyecto = file(&path("proyecto"), true);
+    // La carpeta está sincronizada pero contiene un archivo con error
+    assert_eq!(unsafe { harness.emblems_after_update(proyecto) }, ["emblem-gdrivexp-error"]);
+}
+
+#[test]
+fn cancelled_update_is_never_completed() {
+    let harness = harness();
+    let borrador = file(&path("borrador.txt"), false);
+    let closure = closure();
+
+    let (result, handle) = unsafe { harness.update_file_info(borrador, closure) };
+    assert_eq!(result, IN_PROGRESS);
+    unsafe {
+        let iface: &InfoProviderInterface = harness.interface(types().info_provider);
+        iface.cancel_update.unwrap()(harness.provider, handle as gpointer);
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(300);
+    while Instant::now() < deadline {
+        pump();
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(completion(closure), None);
+    assert!(fake_file(borrador).emblems.is_empty());
+
+    // Una actualización posterior sí se completa
+    assert_eq!(unsafe { harness.emblems_after_update(borrador) }, ["emblem-gdrivexp-local"]);
+}
+
+#[test]
+fn file_items_follow_the_selection_and_reach_the_daemon() {
+    let harness = harness();
+    let selection = [
+        file(&path("presupuesto.xlsx"), false),
+        file(&path("foto.jpg"), false),
+        file(&path("plano.dwg"), false),
+        file(&path("notas.txt"), false),
+        file("/home/ana/ajeno.txt", false),
+    ];
+    let items = unsafe { harness.file_items(&selection) };
+    assert_eq!(
+        names(&items),
+        [
+            "gdrivexp::free_space",
+            "gdrivexp::keep_local",
+            "gdrivexp::resolve_conflict",
+            "gdrivexp::retry_sync",
+            "gdrivexp::error_detail_0",
+        ]
+    );
+    assert_eq!(menu_item(items[0]).label, "Free up space");
+    assert!(menu_item(items[0]).sensitive);
+
+    // El motivo del error es informativo; su NUL no rompe la etiqueta
+    let detail = menu_item(find(&items, "gdrivexp::error_detail_0"));
+    assert!(!detail.sensitive);
+    assert_eq!(detail.label, "Error: Cuota\u{FFFD}excedida");
+    assert_eq!(detail.tip, "Cuota\u{FFFD}excedida");
+
+    let submenu = menu_item(find(&items, "gdrivexp::resolve_conflict")).submenu.expect("conflict submenu");
+    let choices: Vec<*mut GObject> =
+        with_registry(|r| r.menus[&submenu].iter().map(|&item| item as *mut GObject).collect());
+    assert_eq!(
+        names(&choices),
+        ["gdrivexp::conflict_keep_local", "gdrivexp::conflict_keep_remote", "gdrivexp::conflict_keep_both"]
+    );
+
+    activate(find(&items, "gdrivexp::free_space"));
+    assert!(harness.wait_request(|r| matches!(r, IpcRequest::SetOnlineOnly { path } if is(path, "presupuesto.xlsx"))));
+    activate(find(&items, "gdrivexp::keep_local"));
+    assert!(harness.wait_request(|r| matches!(r, IpcRequest::SetLocalOnline { path } if is(path, "foto.jpg"))));
+    activate(choices[2]);
+    assert!(harness.wait_request(|r| matches!(
+        r,
+        IpcRequest::ResolveConflict { path, resolution: ConflictResolution::KeepBoth } if is(path, "plano.dwg")
+    )));
+    activate(find(&items, "gdrivexp::retry_sync"));
+    assert!(harness.wait_request(
+        |r| matches!(r, IpcRequest::RetrySync { paths } if paths.len() == 1 && is(&paths[0], "notas.txt"))
+    ));
+
+    release(items);
+}
+
+#[test]
+fn folder_items_show_the_impact_on_the_first_click() {
+    let harness = harness();
+    let items = unsafe { harness.file_items(&[file(&path("proyecto"), true)]) };
+    assert_eq!(names(&items), ["gdrivexp::folder_keep_local", "gdrivexp::folder_free_space"]);
+
+    // La carpeta y datos.csv siguen en local: liberar afecta a los dos
+    let free = menu_item(find(&items, "gdrivexp::folder_free_space")).label;
+    assert!(free.starts_with("Free up space for entire folder (2 files, "), "{}", free);
+    release(items);
+}
+
+#[test]
+fn background_items_only_for_tracked_folders() {
+    let harness = harness();
+    let items = unsafe { harness.background_items(file(ROOT, true)) };
+    assert_eq!(
+        names(&items),
+        ["gdrivexp::bg_keep_local", "gdrivexp::bg_free_space", "gdrivexp::bg_sync_now", "gdrivexp::bg_pause_sync"]
+    );
+
+    activate(find(&items, "gdrivexp::bg_sync_now"));
+    let root = |path: &str| path == ROOT || path == format!("file://{}", ROOT);
+    assert!(harness.wait_request(|r| matches!(r, IpcRequest::SyncNow { path } if root(path))));
+    activate(find(&items, "gdrivexp::bg_pause_sync"));
+    assert!(harness.wait_request(|r| matches!(r, IpcRequest::PauseSync { path } if root(path))));
+    release(items);
+
+    assert!(unsafe { harness.background_items(file("/home/ana", true)) }.is_empty());
+}
+
+#[test]
+fn columns_read_the_attributes_set_with_the_emblems() {
+    let harness = harness();
+    let columns = unsafe {
+        let iface: &ColumnProviderInterface = harness.interface(types().column_provider);
+        take_list(iface.get_columns.unwrap()(harness.provider))
+    };
+    let attributes: Vec<String> =
+        with_registry(|r| columns.iter().map(|&column| r.columns[&(column as usize)][1].clone()).collect());
+    assert_eq!(attributes, ["gdrivexp_status", "gdrivexp_last_synced", "gdrivexp_owner", "gdrivexp_shared"]);
+    let names: Vec<String> =
+        with_registry(|r| columns.iter().map(|&column| r.columns[&(column as usize)][0].clone()).collect());
+    assert_eq!(names[0], "GDriveXP::status_column");
+}
+
+#[test]
+fn properties_section_is_filled_in_the_main_loop() {
+    let harness = harness();
+    let iface: &PropertiesModelProviderInterface = unsafe { harness.interface(types().properties_model_provider) };
+    let contrato = file(&path("contrato.pdf"), false);
+
+    let models = unsafe { with_list(&[contrato], |list| take_list(iface.get_models.unwrap()(harness.provider, list))) };
+    assert_eq!(models.len(), 1);
+    let (title, store) = with_registry(|r| r.models[&(models[0] as usize)].clone());
+    assert_eq!(title, "Google Drive");
+    let store = store as *mut gio_sys::GListModel;
+
+    assert!(pump_until(|| unsafe { gio_sys::g_list_model_get_n_items(store) } > 0), "section never filled");
+    let rows: Vec<(String, String)> = (0..unsafe { gio_sys::g_list_model_get_n_items(store) })
+        .map(|index| unsafe {
+            let item = gio_sys::g_list_model_get_item(store, index);
+            let row = with_registry(|r| r.properties_items[&(item as usize)].clone());
+            gobject_sys::g_object_unref(item as *mut GObject);
+            row
+        })
+        .collect();
+    assert_eq!(rows[0], ("Status".to_string(), "Synced".to_string()));
+    assert!(rows.contains(&("Drive ID".to_string(), "1AbC".to_string())));
+
+    // Varios archivos, o uno que el daemon no rastrea: sin sección
+    let pair = [contrato, file(&path("informe.pdf"), false)];
+    assert!(unsafe { with_list(&pair, |list| iface.get_models.unwrap()(harness.provider, list)) }.is_null());
+    let untracked = [file(&path("desconocido.txt"), false)];
+    assert!(unsafe { with_list(&untracked, |list| iface.get_models.unwrap()(harness.provider, list)) }.is_null());
+}
+
+#[test]
+fn daemon_changes_invalidate_loaded_files() {
+    let harness = harness();
+    let avisos = file(&path("avisos.txt"), false);
+    assert_eq!(unsafe { harness.emblems_after_update(avisos) }, ["emblem-gdrivexp-synced"]);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while harness.daemon.subscribers() == 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    harness.daemon.set_status(&path("avisos.txt"), SyncStatus::Error);
+    assert!(pump_until(|| fake_file(avisos).invalidations > 0), "file never invalidated");
+
+    // Nautilus vuelve a preguntar: el estado en caché ya se desalojó
+    with_registry(|r| r.files.get_mut(&(avisos as usize)).unwrap().emblems.clear());
+    assert_eq!(unsafe { harness.emblems_after_update(avisos) }, ["emblem-gdrivexp-error"]);
+}